@@ -0,0 +1,171 @@
+use egui::{pos2, Color32, Pos2, Rect, Shape, Stroke};
+
+use crate::objects::Objects;
+
+// how close on screen an edge or center has to be to snap to a neighbor's
+pub const GUIDE_DISTANCE: f32 = 6.0;
+
+// grid lines closer than this on screen are not drawn
+const MIN_GRID_GAP: f32 = 6.0;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Alignment {
+    Left,
+    Center,
+    Right,
+    Top,
+    Middle,
+    Bottom
+}
+
+impl Alignment {
+    pub const ALL: [(Alignment, &'static str); 6] = [
+        (Alignment::Left, "Align Left"),
+        (Alignment::Center, "Align Centers"),
+        (Alignment::Right, "Align Right"),
+        (Alignment::Top, "Align Top"),
+        (Alignment::Middle, "Align Middles"),
+        (Alignment::Bottom, "Align Bottom")
+    ];
+
+    fn horizontal(&self) -> bool { matches!(self, Alignment::Left | Alignment::Center | Alignment::Right) }
+
+    // the edge or center of an area this lines up
+    fn line(&self, area: Rect) -> f32 {
+        match self {
+            Alignment::Left => area.min.x,
+            Alignment::Center => area.center().x,
+            Alignment::Right => area.max.x,
+            Alignment::Top => area.min.y,
+            Alignment::Middle => area.center().y,
+            Alignment::Bottom => area.max.y
+        }
+    }
+}
+
+// rounds a position to the nearest grid line
+pub fn snap(value: f32, spacing: f32) -> f32 {
+    if spacing > 0.0 { (value / spacing).round() * spacing } else { value }
+}
+
+// grid lines covering the canvas, origin is where the canvas center is on screen and gap is the spacing on screen
+pub fn grid(clip: Rect, origin: Pos2, gap: f32) -> Vec<Shape> {
+    if gap < MIN_GRID_GAP { return vec![] }
+    let stroke = Stroke { width: 1.0, color: Color32::from_gray(230) };
+    let first = |min: f32, origin: f32| origin - ((origin - min) / gap).floor() * gap;
+    let columns = std::iter::successors(Some(first(clip.min.x, origin.x)), |x| Some(x + gap)).take_while(|x| *x <= clip.max.x);
+    let rows = std::iter::successors(Some(first(clip.min.y, origin.y)), |y| Some(y + gap)).take_while(|y| *y <= clip.max.y);
+    columns.map(|x| Shape::LineSegment { points: [pos2(x, clip.min.y), pos2(x, clip.max.y)], stroke })
+        .chain(rows.map(|y| Shape::LineSegment { points: [pos2(clip.min.x, y), pos2(clip.max.x, y)], stroke }))
+        .collect()
+}
+
+// for each axis, how far to move an area so its edges or center line up with another area nearby, and the line they meet on
+pub fn line_up(area: Rect, others: &[Rect], distance: f32) -> [Option<(f32, f32)>; 2] {
+    let lines = |area: Rect| [[area.min.x, area.center().x, area.max.x], [area.min.y, area.center().y, area.max.y]];
+    let own = lines(area);
+    [0, 1].map(|axis| {
+        others.iter()
+            .flat_map(|other| lines(*other)[axis])
+            .flat_map(|line| own[axis].map(|at| (line - at, line)))
+            .filter(|(shift, _)| shift.abs() <= distance)
+            .min_by(|a, b| a.0.abs().total_cmp(&b.0.abs()))
+    })
+}
+
+// moves objects and trees so their edges or centers are on the same line, the furthest edge or the middle of them all
+pub fn align(objects: &mut Objects, ids: &[u32], alignment: Alignment) {
+    let Some(line) = ids.iter().filter_map(|id| objects.item_bounds(*id)).reduce(|a, b| a.union(b)).map(|area| alignment.line(area)) else { return };
+    ids.iter().for_each(|id| {
+        let (Some(area), Some((x, y))) = (objects.item_bounds(*id), objects.position(*id)) else { return };
+        let shift = line - alignment.line(area);
+        if alignment.horizontal() { objects.set_position(*id, x + shift, y); } else { objects.set_position(*id, x, y + shift); }
+    });
+}
+
+// spaces the centers of objects and trees evenly between the two furthest apart, across or down
+pub fn distribute(objects: &mut Objects, ids: &[u32], horizontal: bool) {
+    let center = |area: Rect| if horizontal { area.center().x } else { area.center().y };
+    let mut items = ids.iter().filter_map(|id| Some((*id, center(objects.item_bounds(*id)?)))).collect::<Vec<(u32, f32)>>();
+    if items.len() < 3 { return }
+    items.sort_by(|a, b| a.1.total_cmp(&b.1));
+    let first = items[0].1;
+    let step = (items[items.len() - 1].1 - first) / (items.len() - 1) as f32;
+    items.iter().enumerate().for_each(|(idx, (id, at))| {
+        let Some((x, y)) = objects.position(*id) else { return };
+        let shift = first + step * idx as f32 - at;
+        if horizontal { objects.set_position(*id, x + shift, y); } else { objects.set_position(*id, x, y + shift); }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::objects::ObjectType;
+
+    // entities of the given sizes centered at the given positions
+    fn entities(items: &[(f32, f32, f32, f32)]) -> (Objects, Vec<u32>) {
+        let mut objects = Objects::default();
+        let ids = items.iter().map(|(x, y, width, height)| {
+            let entity = objects.add(ObjectType::Entity, *x, *y);
+            (entity.width, entity.height) = (*width, *height);
+            entity.id
+        }).collect();
+        (objects, ids)
+    }
+
+    fn bounds(objects: &Objects, ids: &[u32]) -> Vec<Rect> { ids.iter().filter_map(|id| objects.item_bounds(*id)).collect() }
+
+    #[test]
+    fn positions_snap_to_the_nearest_line() {
+        assert_eq!(snap(29.0, 20.0), 20.0);
+        assert_eq!(snap(31.0, 20.0), 40.0);
+        assert_eq!(snap(-11.0, 20.0), -20.0);
+        // without a spacing there is no grid to snap to
+        assert_eq!(snap(31.0, 0.0), 31.0);
+    }
+
+    #[test]
+    fn the_nearest_line_within_reach_is_lined_up_with() {
+        let area = Rect::from_min_max(pos2(0.0, 0.0), pos2(20.0, 20.0));
+        let right = Rect::from_min_max(pos2(23.0, 100.0), pos2(43.0, 120.0));
+        let left = Rect::from_min_max(pos2(-29.0, 100.0), pos2(1.0, 120.0));
+        let far = Rect::from_min_max(pos2(27.0, 27.0), pos2(47.0, 47.0));
+
+        // the right edge moves onto the left edge of the area beside it, nothing lines up down
+        assert_eq!(line_up(area, &[right], GUIDE_DISTANCE), [Some((3.0, 23.0)), None]);
+        // the left edges are closer
+        assert_eq!(line_up(area, &[right, left], GUIDE_DISTANCE), [Some((1.0, 1.0)), None]);
+        assert_eq!(line_up(area, &[far], GUIDE_DISTANCE), [None, None]);
+    }
+
+    #[test]
+    fn aligning_moves_everything_to_the_outermost_edge_or_middle() {
+        let (mut objects, ids) = entities(&[(0.0, 0.0, 40.0, 20.0), (100.0, 50.0, 80.0, 40.0), (30.0, 200.0, 20.0, 20.0)]);
+        align(&mut objects, &ids, Alignment::Left);
+        assert!(bounds(&objects, &ids).iter().all(|area| area.min.x == -20.0));
+        assert_eq!(objects.position(ids[1]), Some((20.0, 50.0)));
+
+        align(&mut objects, &ids, Alignment::Bottom);
+        assert!(bounds(&objects, &ids).iter().all(|area| area.max.y == 210.0));
+
+        // centers meet in the middle of everything, which stays where it was
+        align(&mut objects, &ids, Alignment::Center);
+        assert!(bounds(&objects, &ids).iter().all(|area| area.center().x == 20.0));
+        assert!(ids.iter().all(|id| objects.position(*id).is_some_and(|(x, _)| x == 20.0)));
+    }
+
+    #[test]
+    fn distributing_spaces_centers_evenly() {
+        let (mut objects, ids) = entities(&[(0.0, 0.0, 40.0, 20.0), (90.0, 10.0, 80.0, 40.0), (20.0, 20.0, 20.0, 20.0), (300.0, 30.0, 20.0, 20.0)]);
+        distribute(&mut objects, &ids, true);
+        let positions = ids.iter().filter_map(|id| objects.position(*id)).collect::<Vec<(f32, f32)>>();
+        // the ends stay, the others keep their order and only move across
+        assert_eq!(positions, [(0.0, 0.0), (200.0, 10.0), (100.0, 20.0), (300.0, 30.0)]);
+
+        // two objects are already as evenly spaced as they can be
+        let (mut objects, ids) = entities(&[(0.0, 0.0, 40.0, 20.0), (90.0, 10.0, 80.0, 40.0)]);
+        distribute(&mut objects, &ids, false);
+        assert_eq!(ids.iter().filter_map(|id| objects.position(*id)).collect::<Vec<(f32, f32)>>(), [(0.0, 0.0), (90.0, 10.0)]);
+    }
+}
@@ -1,66 +1,549 @@
+use egui::{pos2, vec2, Rect};
 use serde::{Deserialize, Serialize};
 
-pub const NODE_COUNT: usize = 3;
+use crate::bplustree::BPlusNode;
+
+// the smallest order a tree can have, and the order of trees saved before it was stored
+pub const MIN_ORDER: usize = 3;
+pub const DEFAULT_ORDER: usize = 4;
+
+// sizes used to draw trees, keys use the same monospace font as objects
+pub const KEY_CHAR_WIDTH: f32 = 8.4;
+const POINTER_WIDTH: f32 = 12.0;
+const NODE_HEIGHT: f32 = 30.0;
+const LEVEL_SPACING: f32 = 60.0;
+const NODE_SPACING: f32 = 20.0;
+const MAX_DRAWN_SLOTS: usize = 6;
+
+// a tree placed on the canvas, x and y are the center of its root node
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct BTree {
+    pub id: u32,
+    pub x: f32,
+    pub y: f32,
+    #[serde(flatten)]
+    pub root: TreeRoot,
+    #[serde(skip)]
+    pub dragging: bool
+}
+
+// a node of a tree as it is drawn, relative to the canvas center
+#[derive(Clone, Debug)]
+pub struct NodeShape {
+    pub rect: Rect,
+    // a slot for every key the node can hold, empty slots have no key
+    pub keys: Vec<(Rect, Option<String>)>,
+    // a cell between each key, with the index of the child it points to
+    pub pointers: Vec<(Rect, Option<usize>)>,
+    // the leaf to the right of this one, for trees with linked leaves
+    pub next: Option<usize>
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TreeKind {
+    #[default]
+    BMinus,
+    BPlus
+}
+
+impl std::fmt::Display for TreeKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TreeKind::BMinus => write!(f, "B-tree"),
+            TreeKind::BPlus => write!(f, "B+ tree")
+        }
+    }
+}
+
+// how keys are compared, kept on every node like the order
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum KeyOrder {
+    // numbers by value, anything that is not a number comes after them
+    #[default]
+    Numeric,
+    // character by character, ignoring case
+    Lexicographic
+}
+
+impl KeyOrder {
+    pub fn compare(&self, a: &str, b: &str) -> std::cmp::Ordering {
+        let (a, b) = (a.trim(), b.trim());
+        let words = || a.to_lowercase().cmp(&b.to_lowercase());
+        match self {
+            KeyOrder::Numeric => match (a.parse::<f64>(), b.parse::<f64>()) {
+                (Ok(x), Ok(y)) => x.total_cmp(&y),
+                (Ok(_), Err(_)) => std::cmp::Ordering::Less,
+                (Err(_), Ok(_)) => std::cmp::Ordering::Greater,
+                (Err(_), Err(_)) => words()
+            },
+            KeyOrder::Lexicographic => words()
+        }.then_with(|| a.cmp(b))
+    }
+}
+
+// what kind of tree to build and how its keys are read from text
+#[derive(Clone, Debug)]
+pub struct TreeOptions {
+    pub kind: TreeKind,
+    pub order: usize,
+    pub ordering: KeyOrder,
+    // what separates keys, empty splits on any whitespace
+    pub delimiter: String,
+    // only this many keys from the start are used
+    pub limit: Option<usize>
+}
+
+impl Default for TreeOptions {
+    fn default() -> Self {
+        Self { kind: TreeKind::BMinus, order: DEFAULT_ORDER, ordering: KeyOrder::Numeric, delimiter: "\n".into(), limit: None }
+    }
+}
+
+impl TreeOptions {
+    // reads keys from text, trimming spaces and carriage returns, then sorts them and drops duplicates
+    pub fn keys(&self, text: &str) -> Result<Vec<String>, String> {
+        let keys = if self.delimiter.is_empty() { text.split_whitespace().collect::<Vec<&str>>() } else { text.split(self.delimiter.as_str()).collect() };
+        let mut keys = keys.into_iter()
+            .map(|key| key.trim().to_string())
+            .filter(|key| !key.is_empty())
+            .take(self.limit.unwrap_or(usize::MAX))
+            .collect::<Vec<String>>();
+
+        if self.ordering == KeyOrder::Numeric {
+            if let Some(word) = keys.iter().find(|key| key.parse::<f64>().is_err()) {
+                return Err(format!("{word} is not a number, use lexicographic ordering for words"))
+            }
+        }
+        sort_keys(&mut keys, self.ordering);
+        Ok(keys)
+    }
+}
+
+// the root node of either kind of tree, saved as root or plus_root so older files still load
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub enum TreeRoot {
+    #[serde(rename = "root")]
+    BMinus(BMinusNode),
+    #[serde(rename = "plus_root")]
+    BPlus(BPlusNode)
+}
+
+impl TreeRoot {
+    // builds a tree from keys sorted in the options ordering
+    pub fn create(keys: Vec<String>, options: &TreeOptions) -> Self {
+        match options.kind {
+            TreeKind::BMinus => TreeRoot::BMinus(BMinusNode::create(keys, options.order, options.ordering)),
+            TreeKind::BPlus => TreeRoot::BPlus(BPlusNode::create(keys, options.order, options.ordering))
+        }
+    }
+
+    // the options that build a tree like this one
+    pub fn options(&self) -> TreeOptions {
+        let ordering = match self {
+            TreeRoot::BMinus(root) => root.ordering,
+            TreeRoot::BPlus(root) => root.ordering
+        };
+        TreeOptions { kind: self.kind(), order: self.order(), ordering, ..TreeOptions::default() }
+    }
+
+    pub fn kind(&self) -> TreeKind {
+        match self {
+            TreeRoot::BMinus(_) => TreeKind::BMinus,
+            TreeRoot::BPlus(_) => TreeKind::BPlus
+        }
+    }
+
+    pub fn order(&self) -> usize {
+        match self {
+            TreeRoot::BMinus(root) => root.order,
+            TreeRoot::BPlus(root) => root.order
+        }
+    }
+
+    // gets every key stored in the tree in order
+    pub fn all_keys(&self) -> Vec<String> {
+        match self {
+            TreeRoot::BMinus(root) => root.all_keys(),
+            TreeRoot::BPlus(root) => root.all_keys()
+        }
+    }
+
+    pub fn search(&self, key: &str) -> Vec<TraceStep> {
+        match self {
+            TreeRoot::BMinus(root) => root.search(key),
+            TreeRoot::BPlus(root) => root.search(key)
+        }
+    }
+
+    pub fn insert(&mut self, key: &str) -> Vec<TraceStep> {
+        match self {
+            TreeRoot::BMinus(root) => root.insert(key),
+            TreeRoot::BPlus(root) => root.insert(key)
+        }
+    }
+
+    pub fn delete(&mut self, key: &str) -> Vec<TraceStep> {
+        match self {
+            TreeRoot::BMinus(root) => root.delete(key),
+            TreeRoot::BPlus(root) => root.delete(key)
+        }
+    }
+
+    pub fn check(&self) -> Vec<Violation> {
+        match self {
+            TreeRoot::BMinus(root) => root.check(),
+            TreeRoot::BPlus(root) => root.check()
+        }
+    }
+
+    // gets the keys of every node and whether it is a leaf, in the same order as BTree::shapes
+    pub fn nodes(&self) -> Vec<(&[String], bool)> {
+        match self {
+            TreeRoot::BMinus(root) => root.nodes().into_iter().map(|node| (node.keys(), node.is_leaf())).collect(),
+            TreeRoot::BPlus(root) => root.nodes().into_iter().map(|node| (node.keys(), node.is_leaf())).collect()
+        }
+    }
+}
+
+impl BTree {
+    pub fn new(id: u32, x: f32, y: f32, root: TreeRoot) -> Self {
+        Self { id, x, y, root, dragging: false }
+    }
+
+    // gets every node with its key slots and child pointers, parents come before their children
+    pub fn shapes(&self) -> Vec<NodeShape> {
+        let slot_width = self.root.nodes().iter()
+            .flat_map(|(keys, _)| keys.iter())
+            .map(|key| key.trim().chars().count() as f32 * KEY_CHAR_WIDTH + 12.0)
+            .fold(30.0, f32::max);
+
+        let mut shapes = Vec::new();
+        match &self.root {
+            TreeRoot::BMinus(root) => { place_node(root, -root.subtree_width(slot_width) / 2.0, 0.0, slot_width, &mut shapes); },
+            TreeRoot::BPlus(root) => {
+                place_node(root, -root.subtree_width(slot_width) / 2.0, 0.0, slot_width, &mut shapes);
+
+                // leaves are linked left to right, and come in that order
+                let leaves = self.root.nodes().iter().enumerate().filter(|(_, (_, leaf))| *leaf).map(|(idx, _)| idx).collect::<Vec<usize>>();
+                leaves.windows(2).for_each(|pair| shapes[pair[0]].next = Some(pair[1]));
+            }
+        }
+
+        // move so the root is centered on the trees position
+        let offset = vec2(self.x, self.y) - shapes[0].rect.center().to_vec2();
+        shapes.iter_mut().for_each(|shape| {
+            shape.rect = shape.rect.translate(offset);
+            shape.keys.iter_mut().for_each(|(rect, _)| *rect = rect.translate(offset));
+            shape.pointers.iter_mut().for_each(|(rect, _)| *rect = rect.translate(offset));
+        });
+        shapes
+    }
+
+    // gets the area covered by the tree, relative to the canvas center
+    pub fn bounds(&self) -> Rect {
+        self.shapes().iter().map(|shape| shape.rect).reduce(|a, b| a.union(b)).unwrap_or(Rect::NOTHING)
+    }
+}
+
+// places a node centered above its children, returning its index
+fn place_node<N: Node>(node: &N, left: f32, top: f32, slot_width: f32, shapes: &mut Vec<NodeShape>) -> usize {
+    let width = node.subtree_width(slot_width);
+    let node_width = node.node_width(slot_width);
+    let min = pos2(left + (width - node_width) / 2.0, top);
+    let idx = shapes.len();
+
+    // cells alternate pointer, key, pointer, ... ending with a pointer
+    let slots = node.slots();
+    let keys = (0 .. slots).map(|slot| {
+        let x = min.x + POINTER_WIDTH + slot as f32 * (slot_width + POINTER_WIDTH);
+        (Rect::from_min_size(pos2(x, top), vec2(slot_width, NODE_HEIGHT)), node.keys().get(slot).map(|key| key.trim().to_string()))
+    }).collect();
+    let pointers = (0 ..= slots).map(|slot| {
+        let x = min.x + slot as f32 * (slot_width + POINTER_WIDTH);
+        (Rect::from_min_size(pos2(x, top), vec2(POINTER_WIDTH, NODE_HEIGHT)), None)
+    }).collect();
+    shapes.push(NodeShape { rect: Rect::from_min_size(min, vec2(node_width, NODE_HEIGHT)), keys, pointers, next: None });
+
+    // children share the width under this node
+    let children_width = node.children().iter().map(|child| child.subtree_width(slot_width)).sum::<f32>() + NODE_SPACING * node.children().len().saturating_sub(1) as f32;
+    let mut x = left + (width - children_width) / 2.0;
+    node.children().iter().enumerate().for_each(|(slot, child)| {
+        let child_idx = place_node(child, x, top + NODE_HEIGHT + LEVEL_SPACING, slot_width, shapes);
+        if let Some(pointer) = shapes[idx].pointers.get_mut(slot) { pointer.1 = Some(child_idx); }
+        x += child.subtree_width(slot_width) + NODE_SPACING;
+    });
+
+    idx
+}
+
+// what drawing needs from a node, shared by both kinds of tree
+pub trait Node: Sized {
+    fn keys(&self) -> &[String];
+    fn children(&self) -> &[Self];
+    fn order(&self) -> usize;
+    fn ordering(&self) -> KeyOrder;
+
+    // the fewest keys a node other than the root can hold
+    fn min_keys(&self) -> usize;
+
+    // whether keys equal to a separator are kept on its right, like b+ trees do
+    const SEPARATOR_ON_RIGHT: bool = false;
+
+    fn is_leaf(&self) -> bool { self.children().is_empty() }
+
+    // the most keys a node can hold
+    fn max_keys(&self) -> usize { self.order() - 1 }
+
+    // gets every node, parents before their children, in the same order as BTree::shapes
+    fn nodes(&self) -> Vec<&Self> {
+        let mut nodes = vec![self];
+        self.children().iter().for_each(|child| nodes.extend(child.nodes()));
+        nodes
+    }
+
+    // small orders show every slot so empty ones can be seen, big orders only show what is used
+    fn slots(&self) -> usize {
+        if self.max_keys() <= MAX_DRAWN_SLOTS { self.max_keys() } else { self.keys().len().max(self.children().len().saturating_sub(1)).max(1) }
+    }
+
+    fn node_width(&self, slot_width: f32) -> f32 {
+        self.slots() as f32 * slot_width + (self.slots() + 1) as f32 * POINTER_WIDTH
+    }
+
+    // width needed to draw this node and everything below it
+    fn subtree_width(&self, slot_width: f32) -> f32 {
+        let children = self.children().iter().map(|child| child.subtree_width(slot_width)).sum::<f32>()
+            + NODE_SPACING * self.children().len().saturating_sub(1) as f32;
+        self.node_width(slot_width).max(children)
+    }
+}
+
+impl Node for BMinusNode {
+    fn keys(&self) -> &[String] { &self.keys }
+    fn children(&self) -> &[Self] { &self.children }
+    fn order(&self) -> usize { self.order }
+    fn ordering(&self) -> KeyOrder { self.ordering }
+    fn min_keys(&self) -> usize { self.order.div_ceil(2) - 1 }
+}
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
 pub struct BMinusNode {
     pub keys: Vec<String>,
-    pub children: Vec<BMinusNode>
+    pub children: Vec<BMinusNode>,
+    // the most children a node can have
+    #[serde(default = "default_order")]
+    pub order: usize,
+    #[serde(default)]
+    pub ordering: KeyOrder
+}
+
+pub fn default_order() -> usize { DEFAULT_ORDER }
+
+// the most keys a tree of the given order and height can hold, a height of 0 is a single leaf
+fn capacity(order: usize, height: usize) -> usize {
+    (0 ..= height).fold(1usize, |total, _| total.saturating_mul(order)) - 1
 }
 
 impl BMinusNode {
-    pub fn create(input: Vec<String>) -> Self {
-        match input.len() {
-            0 => panic!("Must be given input!"),
-            1 ..= 3 => Self { keys: input, children: vec![] },
-            4 ..= 7 => {
-                let mut mid = input.len() / 2;
-                if input.len() % 2 == 0 { mid -= 1; }
-                println!("Mid {mid}");
-                Self {
-                    keys: vec![input[mid].clone()],
-                    children: vec![
-                        BMinusNode::create(input[0..mid].to_vec()),
-                        BMinusNode::create(input[mid + 1 .. input.len()].to_vec())
-                    ]
+    // builds a tree of the given order from sorted keys, with every leaf at the same depth
+    pub fn create(input: Vec<String>, order: usize, ordering: KeyOrder) -> Self {
+        let order = order.max(MIN_ORDER);
+        let mut height = 0;
+        while capacity(order, height) < input.len() { height += 1; }
+        Self::create_at(input, order, ordering, height, true)
+    }
+
+    fn create_at(input: Vec<String>, order: usize, ordering: KeyOrder, height: usize, root: bool) -> Self {
+        if height == 0 { return Self { keys: input, children: vec![], order, ordering } }
+
+        // as few children as will fit the keys, but at least as many as a node needs
+        let fewest = if root { 2 } else { order.div_ceil(2) };
+        let children = (input.len() + 1).div_ceil(capacity(order, height - 1) + 1).clamp(fewest, order);
+
+        // spread the keys between children evenly, a separator goes between each
+        let below = input.len() - (children - 1);
+        let (base, extra) = (below / children, below % children);
+        let mut keys = Vec::new();
+        let mut nodes = Vec::new();
+        let mut rest = input.into_iter();
+        (0 .. children).for_each(|idx| {
+            let size = base + usize::from(idx < extra);
+            nodes.push(Self::create_at(rest.by_ref().take(size).collect(), order, ordering, height - 1, false));
+            if let Some(separator) = rest.next() { keys.push(separator); }
+        });
+
+        Self { keys, children: nodes, order, ordering }
+    }
+
+    // lists every rule of a b-tree the tree breaks
+    pub fn check(&self) -> Vec<Violation> { check_tree(self) }
+
+    // where a key is in this node, or which child it would be under
+    fn find(&self, key: &str) -> Result<usize, usize> {
+        self.keys.binary_search_by(|probe| self.ordering.compare(probe, key))
+    }
+
+    // looks a key up, going down from the root
+    pub fn search(&self, key: &str) -> Vec<TraceStep> {
+        let key = key.trim();
+        let mut trace = Vec::new();
+        let mut node = self;
+        loop {
+            trace.push(TraceStep::Visit { keys: node.keys.clone() });
+            match node.find(key) {
+                Ok(_) => {
+                    trace.push(TraceStep::Found { key: key.to_string() });
+                    return trace
+                },
+                Err(idx) => match node.children.get(idx) {
+                    Some(child) => node = child,
+                    None => {
+                        trace.push(TraceStep::NotFound { key: key.to_string() });
+                        return trace
+                    }
                 }
+            }
+        }
+    }
+
+    // adds a key to its leaf, splitting full nodes on the way back up
+    pub fn insert(&mut self, key: &str) -> Vec<TraceStep> {
+        let mut trace = Vec::new();
+        if let Some((promoted, right)) = self.insert_at(key.trim(), &mut trace) {
+            // the root was split, so the tree grows a level
+            let left = std::mem::replace(self, Self { keys: vec![promoted.clone()], children: vec![], ..*self });
+            self.children = vec![left, right];
+            trace.push(TraceStep::Grow { key: promoted });
+        }
+        trace
+    }
+
+    // returns the promoted key and new right half if this node had to split
+    fn insert_at(&mut self, key: &str, trace: &mut Vec<TraceStep>) -> Option<(String, Self)> {
+        trace.push(TraceStep::Visit { keys: self.keys.clone() });
+        let idx = match self.find(key) {
+            Ok(_) => {
+                trace.push(TraceStep::Duplicate { key: key.to_string() });
+                return None
             },
-            8 ..= 10 => {
-                let (a, b) = match input.len() {
-                    8 => (1, 4),
-                    9 => (1, 5),
-                    10 => (2, 6),
-                    _ => panic!("RUN")
-                };
-
-                Self {
-                    keys: vec![input[a].clone(), input[b].clone()],
-                    children: vec![
-                        BMinusNode::create(input[0 .. a].to_vec()),
-                        BMinusNode::create(input[a + 1 .. b].to_vec()),
-                        BMinusNode::create(input[b + 1 .. input.len()].to_vec()),
-                    ]
-                }
+            Err(idx) => idx
+        };
+
+        if self.is_leaf() {
+            self.keys.insert(idx, key.to_string());
+            trace.push(TraceStep::Insert { key: key.to_string(), keys: self.keys.clone() });
+        } else {
+            let (promoted, right) = self.children[idx].insert_at(key, trace)?;
+            self.keys.insert(idx, promoted);
+            self.children.insert(idx + 1, right);
+        }
+
+        if self.keys.len() <= self.max_keys() { return None }
+
+        // the middle key goes up, the keys after it go to a new node
+        let keys = self.keys.clone();
+        let middle = self.keys.len() / 2;
+        let right_keys = self.keys.split_off(middle + 1);
+        let promoted = self.keys.pop()?;
+        let right_children = if self.is_leaf() { vec![] } else { self.children.split_off(middle + 1) };
+        trace.push(TraceStep::Split { keys, promoted: promoted.clone() });
+        Some((promoted, Self { keys: right_keys, children: right_children, ..*self }))
+    }
+
+    // removes a key, borrowing from or merging with siblings when a node gets too small
+    pub fn delete(&mut self, key: &str) -> Vec<TraceStep> {
+        let mut trace = Vec::new();
+        self.delete_at(key.trim(), &mut trace);
+
+        // an empty root with one child is replaced by it, so the tree shrinks a level
+        if self.keys.is_empty() && self.children.len() == 1 {
+            *self = self.children.remove(0);
+            trace.push(TraceStep::Shrink);
+        }
+        trace
+    }
+
+    fn delete_at(&mut self, key: &str, trace: &mut Vec<TraceStep>) {
+        trace.push(TraceStep::Visit { keys: self.keys.clone() });
+        match (self.find(key), self.is_leaf()) {
+            (Ok(idx), true) => {
+                self.keys.remove(idx);
+                trace.push(TraceStep::Remove { key: key.to_string(), keys: self.keys.clone() });
             },
-            _ => {
-                let (a, b, c) = match input.len() {
-                    11 => (1, 3, 6),
-                    _ => panic!("RUN")
-                };
-
-                Self {
-                    keys: vec![input[a].clone(), input[b].clone(), input[c].clone()],
-                    children: vec![
-                        BMinusNode::create(input[0 .. a].to_vec()),
-                        BMinusNode::create(input[a + 1 .. b].to_vec()),
-                        BMinusNode::create(input[b + 1 .. c].to_vec()),
-                        BMinusNode::create(input[c + 1 .. input.len()].to_vec()),
-                    ]
-                }
+            // keys in inner nodes are swapped with the biggest key to their left, which is in a leaf
+            (Ok(idx), false) => {
+                let Some(predecessor) = self.children[idx].pop_last(trace) else { return };
+                trace.push(TraceStep::Replace { key: key.to_string(), predecessor: predecessor.clone() });
+                self.keys[idx] = predecessor;
+                self.rebalance(idx, trace);
+            },
+            (Err(_), true) => trace.push(TraceStep::NotFound { key: key.to_string() }),
+            (Err(idx), false) => {
+                self.children[idx].delete_at(key, trace);
+                self.rebalance(idx, trace);
             }
         }
     }
 
+    // removes and returns the biggest key under this node
+    fn pop_last(&mut self, trace: &mut Vec<TraceStep>) -> Option<String> {
+        trace.push(TraceStep::Visit { keys: self.keys.clone() });
+        if self.is_leaf() {
+            let key = self.keys.pop()?;
+            trace.push(TraceStep::Remove { key: key.clone(), keys: self.keys.clone() });
+            return Some(key)
+        }
+        let last = self.children.len() - 1;
+        let key = self.children[last].pop_last(trace);
+        self.rebalance(last, trace);
+        key
+    }
+
+    // fixes a child with too few keys, first by borrowing from a sibling, otherwise by merging with one
+    fn rebalance(&mut self, idx: usize, trace: &mut Vec<TraceStep>) {
+        let min = self.min_keys();
+        if self.children[idx].keys.len() >= min { return }
+
+        if idx > 0 && self.children[idx - 1].keys.len() > min {
+            let (left, right) = self.children.split_at_mut(idx);
+            let (left, child) = (&mut left[idx - 1], &mut right[0]);
+            let Some(key) = left.keys.pop() else { return };
+            let separator = std::mem::replace(&mut self.keys[idx - 1], key.clone());
+            child.keys.insert(0, separator.clone());
+            if let Some(moved) = left.children.pop() { child.children.insert(0, moved); }
+            trace.push(TraceStep::Borrow { from: Sibling::Left, key, separator });
+        } else if idx + 1 < self.children.len() && self.children[idx + 1].keys.len() > min {
+            let (left, right) = self.children.split_at_mut(idx + 1);
+            let (child, right) = (&mut left[idx], &mut right[0]);
+            let key = right.keys.remove(0);
+            let separator = std::mem::replace(&mut self.keys[idx], key.clone());
+            child.keys.push(separator.clone());
+            if !right.is_leaf() { child.children.push(right.children.remove(0)); }
+            trace.push(TraceStep::Borrow { from: Sibling::Right, key, separator });
+        } else if self.children.len() > 1 {
+            // merge with the left sibling if there is one, otherwise with the right
+            let left = if idx > 0 { idx - 1 } else { idx };
+            let right = self.children.remove(left + 1);
+            let separator = self.keys.remove(left);
+            let node = &mut self.children[left];
+            trace.push(TraceStep::Merge { left: node.keys.clone(), separator: separator.clone(), right: right.keys.clone() });
+            node.keys.push(separator);
+            node.keys.extend(right.keys);
+            node.children.extend(right.children);
+        }
+    }
+
+    // gets every key in the tree in order
+    pub fn all_keys(&self) -> Vec<String> {
+        let mut keys = Vec::new();
+        (0 .. self.keys.len().max(self.children.len())).for_each(|idx| {
+            if let Some(child) = self.children.get(idx) { keys.extend(child.all_keys()); }
+            if let Some(key) = self.keys.get(idx) { keys.push(key.trim().to_string()); }
+        });
+        keys
+    }
+
     pub fn debug(&self) {
         self.debug_internal(String::new());
     }
@@ -69,10 +552,213 @@ impl BMinusNode {
         print!("{header}[");
         self.keys.iter().enumerate().for_each(|a| {
             print!("{}", a.1.replace("\r", "").replace("\n", ""));
-            if a.0 < NODE_COUNT - 1 { print!(", ") }
+            if a.0 < self.keys.len() - 1 { print!(", ") }
         });
         println!("]");
         let header = format!("{header} - ");
         self.children.iter().for_each(|a| a.debug_internal(header.clone()));
     }
-}
\ No newline at end of file
+}
+
+// which side a key was borrowed from
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Sibling {
+    Left,
+    Right
+}
+
+// one thing that happened while searching, inserting or deleting
+#[derive(Clone, Debug, PartialEq)]
+pub enum TraceStep {
+    Visit { keys: Vec<String> },
+    Found { key: String },
+    NotFound { key: String },
+    Duplicate { key: String },
+    Insert { key: String, keys: Vec<String> },
+    Split { keys: Vec<String>, promoted: String },
+    SplitLeaf { keys: Vec<String>, copied: String },
+    Grow { key: String },
+    Remove { key: String, keys: Vec<String> },
+    Replace { key: String, predecessor: String },
+    Borrow { from: Sibling, key: String, separator: String },
+    BorrowLeaf { from: Sibling, key: String, separator: String },
+    Merge { left: Vec<String>, separator: String, right: Vec<String> },
+    MergeLeaves { left: Vec<String>, separator: String, right: Vec<String> },
+    Shrink
+}
+
+fn node_text(keys: &[String]) -> String { format!("[{}]", keys.join(", ")) }
+
+impl std::fmt::Display for TraceStep {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TraceStep::Visit { keys } => write!(f, "visit node {}", node_text(keys)),
+            TraceStep::Found { key } => write!(f, "found {key}"),
+            TraceStep::NotFound { key } => write!(f, "{key} is not in the tree"),
+            TraceStep::Duplicate { key } => write!(f, "{key} is already in the tree"),
+            TraceStep::Insert { key, keys } => write!(f, "insert {key} into leaf {}", node_text(keys)),
+            TraceStep::Split { keys, promoted } => write!(f, "split node {} promoting {promoted}", node_text(keys)),
+            TraceStep::SplitLeaf { keys, copied } => write!(f, "split leaf {} copying {copied} up", node_text(keys)),
+            TraceStep::Grow { key } => write!(f, "new root [{key}]"),
+            TraceStep::Remove { key, keys } => write!(f, "remove {key} from leaf, leaving {}", node_text(keys)),
+            TraceStep::Replace { key, predecessor } => write!(f, "replace {key} with its predecessor {predecessor}"),
+            TraceStep::Borrow { from, key, separator } => {
+                let side = match from { Sibling::Left => "left", Sibling::Right => "right" };
+                write!(f, "borrow {key} from the {side} sibling, {separator} moves down")
+            },
+            TraceStep::BorrowLeaf { from, key, separator } => {
+                let side = match from { Sibling::Left => "left", Sibling::Right => "right" };
+                write!(f, "borrow {key} from the {side} leaf, the separator becomes {separator}")
+            },
+            TraceStep::Merge { left, separator, right } => write!(f, "merge {} and {} around {separator}", node_text(left), node_text(right)),
+            TraceStep::MergeLeaves { left, separator, right } => write!(f, "merge leaves {} and {}, dropping separator {separator}", node_text(left), node_text(right)),
+            TraceStep::Shrink => write!(f, "root is empty, its only child becomes the root")
+        }
+    }
+}
+
+// a rule a tree breaks, the path is the index of each child taken from the root
+#[derive(Clone, Debug, PartialEq)]
+pub struct Violation {
+    pub path: Vec<usize>,
+    pub message: String
+}
+
+impl std::fmt::Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "root")?;
+        self.path.iter().try_for_each(|idx| write!(f, " > {idx}"))?;
+        write!(f, ": {}", self.message)
+    }
+}
+
+// checks key counts, key order, separators, child counts and leaf depths
+pub fn check_tree<N: Node>(root: &N) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    let mut leaves = Vec::new();
+    check_node(root, root, &mut vec![], (None, None), &mut leaves, &mut violations);
+
+    // every leaf should be as deep as the first one
+    if let Some(depth) = leaves.first().map(|leaf: &Vec<usize>| leaf.len()) {
+        leaves.into_iter().filter(|leaf| leaf.len() != depth).for_each(|leaf| {
+            let message = format!("leaf is at depth {}, the first leaf is at depth {depth}", leaf.len());
+            violations.push(Violation { path: leaf, message });
+        });
+    }
+    violations
+}
+
+fn count(count: usize, one: &str, many: &str) -> String { format!("{count} {}", if count == 1 { one } else { many }) }
+
+fn check_node<N: Node>(node: &N, root: &N, path: &mut Vec<usize>, bounds: (Option<&str>, Option<&str>), leaves: &mut Vec<Vec<usize>>, violations: &mut Vec<Violation>) {
+    let mut found = Vec::new();
+    let keys = node.keys();
+    let ordering = root.ordering();
+
+    if node.order() != root.order() { found.push(format!("has order {} but the root has order {}", node.order(), root.order())); }
+
+    // the root only needs a key when it has children
+    let min = if !path.is_empty() { node.min_keys() } else if node.is_leaf() { 0 } else { 1 };
+    if keys.len() < min { found.push(format!("has {}, at least {min} are needed", count(keys.len(), "key", "keys"))); }
+    if keys.len() > node.max_keys() { found.push(format!("has {}, a node of order {} holds at most {}", count(keys.len(), "key", "keys"), node.order(), node.max_keys())); }
+
+    keys.windows(2).for_each(|pair| match ordering.compare(&pair[0], &pair[1]) {
+        std::cmp::Ordering::Less => {},
+        std::cmp::Ordering::Equal => found.push(format!("has {} twice", pair[0].trim())),
+        std::cmp::Ordering::Greater => found.push(format!("has {} before {}, keys must be in ascending order", pair[0].trim(), pair[1].trim()))
+    });
+
+    // keys have to be between the separators on either side of the pointer to this node
+    let (lower, upper) = bounds;
+    keys.iter().for_each(|key| {
+        if let Some(lower) = lower {
+            let order = ordering.compare(key, lower);
+            if order.is_lt() || (order.is_eq() && !N::SEPARATOR_ON_RIGHT) { found.push(format!("has {}, which should come after the separator {} above it", key.trim(), lower.trim())); }
+        }
+        if let Some(upper) = upper {
+            if !ordering.compare(key, upper).is_lt() { found.push(format!("has {}, which should come before the separator {} above it", key.trim(), upper.trim())); }
+        }
+    });
+
+    let children = node.children();
+    if node.is_leaf() {
+        leaves.push(path.clone());
+    } else if children.len() != keys.len() + 1 {
+        found.push(format!("has {} for {}, it needs {}", count(children.len(), "child", "children"), count(keys.len(), "key", "keys"), keys.len() + 1));
+    }
+    violations.extend(found.into_iter().map(|message| Violation { path: path.clone(), message }));
+
+    children.iter().enumerate().for_each(|(idx, child)| {
+        let lower = if idx == 0 { lower } else { keys.get(idx - 1).map(|key| key.as_str()).or(lower) };
+        let upper = keys.get(idx).map(|key| key.as_str()).or(upper);
+        path.push(idx);
+        check_node(child, root, path, (lower, upper), leaves, violations);
+        path.pop();
+    });
+}
+
+// sorts keys the way trees keep them and drops duplicates
+pub fn sort_keys(keys: &mut Vec<String>, ordering: KeyOrder) {
+    keys.iter_mut().for_each(|key| *key = key.trim().to_string());
+    keys.retain(|key| !key.is_empty());
+    keys.sort_by(|a, b| ordering.compare(a, b));
+    keys.dedup();
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    // every key from 1 to 60 in a scrambled order
+    pub(crate) fn scrambled() -> Vec<String> { (1 .. 61).map(|idx| (idx * 37 % 61).to_string()).collect() }
+
+    // builds, inserts into and deletes from trees of each small order, checking the rules and keys after every step
+    pub(crate) fn follows_the_rules(kind: TreeKind) {
+        (MIN_ORDER ..= 6).for_each(|order| {
+            let options = TreeOptions { kind, order, ..TreeOptions::default() };
+            (0 .. 40).for_each(|count| {
+                let keys = (0 .. count).map(|key| key.to_string()).collect::<Vec<String>>();
+                let root = TreeRoot::create(keys.clone(), &options);
+                assert_eq!(root.check().len(), 0, "{kind} of order {order} with {count} keys: {:?}", root.check());
+                assert_eq!(root.all_keys(), keys);
+            });
+
+            let mut root = TreeRoot::create(vec![], &options);
+            let mut keys = Vec::new();
+            scrambled().iter().for_each(|key| {
+                root.insert(key);
+                keys.push(key.clone());
+                sort_keys(&mut keys, KeyOrder::Numeric);
+                assert_eq!(root.check().len(), 0, "{kind} of order {order} after inserting {key}: {:?}", root.check());
+                assert_eq!(root.all_keys(), keys);
+            });
+
+            // deleting in another order, and deleting what is not there changes nothing
+            scrambled().iter().rev().chain(["100".to_string()].iter()).for_each(|key| {
+                root.delete(key);
+                keys.retain(|kept| kept != key);
+                assert_eq!(root.check().len(), 0, "{kind} of order {order} after deleting {key}: {:?}", root.check());
+                assert_eq!(root.all_keys(), keys);
+            });
+            assert!(matches!(root.nodes()[..], [(keys, true)] if keys.is_empty()));
+        });
+    }
+
+    #[test]
+    fn b_minus_trees_follow_the_rules() { follows_the_rules(TreeKind::BMinus) }
+
+    #[test]
+    fn duplicates_are_not_inserted() {
+        let mut root = BMinusNode::create(vec!["a".into(), "b".into()], 3, KeyOrder::Lexicographic);
+        assert!(root.insert(" b ").iter().any(|step| matches!(step, TraceStep::Duplicate { .. })));
+        assert_eq!(root.all_keys(), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn broken_trees_are_reported() {
+        let leaf = |keys: &[&str]| BMinusNode { keys: keys.iter().map(|key| key.to_string()).collect(), children: vec![], order: 3, ordering: KeyOrder::Numeric };
+        let root = BMinusNode { keys: vec!["5".into()], children: vec![leaf(&["6"]), leaf(&["7", "8", "9"])], order: 3, ordering: KeyOrder::Numeric };
+        let messages = root.check().iter().map(|violation| violation.to_string()).collect::<Vec<String>>();
+        assert_eq!(messages.len(), 2, "{messages:?}");
+    }
+}
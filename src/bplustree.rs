@@ -0,0 +1,297 @@
+use serde::{Deserialize, Serialize};
+
+use crate::bminustree::{check_tree, default_order, KeyOrder, Node, Sibling, TraceStep, Violation, MIN_ORDER};
+
+// inner nodes only hold separators, every key is kept in a leaf and the leaves are linked left to right
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct BPlusNode {
+    pub keys: Vec<String>,
+    pub children: Vec<BPlusNode>,
+    // the most children a node can have
+    #[serde(default = "default_order")]
+    pub order: usize,
+    #[serde(default)]
+    pub ordering: KeyOrder
+}
+
+impl Node for BPlusNode {
+    fn keys(&self) -> &[String] { &self.keys }
+    fn children(&self) -> &[Self] { &self.children }
+    fn order(&self) -> usize { self.order }
+    fn ordering(&self) -> KeyOrder { self.ordering }
+
+    // leaves split differently so they need more keys than inner nodes
+    fn min_keys(&self) -> usize {
+        if self.is_leaf() { self.order / 2 } else { self.order.div_ceil(2) - 1 }
+    }
+
+    const SEPARATOR_ON_RIGHT: bool = true;
+}
+
+// splits items into the given number of groups, with sizes as close as possible
+fn split_evenly<T>(items: Vec<T>, count: usize) -> Vec<Vec<T>> {
+    let (base, extra) = (items.len() / count, items.len() % count);
+    let mut rest = items.into_iter();
+    (0 .. count).map(|idx| rest.by_ref().take(base + usize::from(idx < extra)).collect()).collect()
+}
+
+impl BPlusNode {
+    // builds a tree of the given order from sorted keys, filling the leaves first and then adding levels above them
+    pub fn create(input: Vec<String>, order: usize, ordering: KeyOrder) -> Self {
+        let order = order.max(MIN_ORDER);
+        let leaves = input.len().div_ceil(order - 1).max(1);
+        let mut level = split_evenly(input, leaves).into_iter()
+            .map(|keys| Self { keys, children: vec![], order, ordering })
+            .collect::<Vec<Self>>();
+
+        // each level holds the one below in as few nodes as possible, until only the root is left
+        while level.len() > 1 {
+            let count = level.len().div_ceil(order);
+            level = split_evenly(level, count).into_iter()
+                .map(|children| Self { keys: children.iter().skip(1).map(|child| child.first_key()).collect(), children, order, ordering })
+                .collect();
+        }
+        level.pop().unwrap_or(Self { keys: vec![], children: vec![], order, ordering })
+    }
+
+    // the smallest key under this node, used as the separator in front of it
+    fn first_key(&self) -> String {
+        match self.children.first() {
+            Some(child) => child.first_key(),
+            None => self.keys.first().cloned().unwrap_or_default()
+        }
+    }
+
+    // lists every rule of a b+ tree the tree breaks
+    pub fn check(&self) -> Vec<Violation> { check_tree(self) }
+
+    // gets every key in the tree in order, which are exactly the keys in the leaves
+    pub fn all_keys(&self) -> Vec<String> {
+        self.nodes().into_iter()
+            .filter(|node| node.is_leaf())
+            .flat_map(|node| node.keys.iter().map(|key| key.trim().to_string()))
+            .collect()
+    }
+
+    fn find(&self, key: &str) -> Result<usize, usize> {
+        self.keys.binary_search_by(|probe| self.ordering.compare(probe, key))
+    }
+
+    // which child a key is under, keys equal to a separator are on its right
+    fn child_for(&self, key: &str) -> usize {
+        match self.find(key) {
+            Ok(idx) => idx + 1,
+            Err(idx) => idx
+        }
+    }
+
+    // looks a key up, always going all the way down to a leaf
+    pub fn search(&self, key: &str) -> Vec<TraceStep> {
+        let key = key.trim();
+        let mut trace = Vec::new();
+        let mut node = self;
+        loop {
+            trace.push(TraceStep::Visit { keys: node.keys.clone() });
+            if node.is_leaf() {
+                trace.push(match node.find(key) {
+                    Ok(_) => TraceStep::Found { key: key.to_string() },
+                    Err(_) => TraceStep::NotFound { key: key.to_string() }
+                });
+                return trace
+            }
+            node = &node.children[node.child_for(key)];
+        }
+    }
+
+    // adds a key to its leaf, splitting full nodes on the way back up
+    pub fn insert(&mut self, key: &str) -> Vec<TraceStep> {
+        let mut trace = Vec::new();
+        if let Some((promoted, right)) = self.insert_at(key.trim(), &mut trace) {
+            // the root was split, so the tree grows a level
+            let left = std::mem::replace(self, Self { keys: vec![promoted.clone()], children: vec![], ..*self });
+            self.children = vec![left, right];
+            trace.push(TraceStep::Grow { key: promoted });
+        }
+        trace
+    }
+
+    // returns the separator and new right half if this node had to split
+    fn insert_at(&mut self, key: &str, trace: &mut Vec<TraceStep>) -> Option<(String, Self)> {
+        trace.push(TraceStep::Visit { keys: self.keys.clone() });
+
+        if self.is_leaf() {
+            let idx = match self.find(key) {
+                Ok(_) => {
+                    trace.push(TraceStep::Duplicate { key: key.to_string() });
+                    return None
+                },
+                Err(idx) => idx
+            };
+            self.keys.insert(idx, key.to_string());
+            trace.push(TraceStep::Insert { key: key.to_string(), keys: self.keys.clone() });
+            if self.keys.len() <= self.max_keys() { return None }
+
+            // a leaf keeps every key, the first key of the new right half is copied up
+            let keys = self.keys.clone();
+            let right = self.keys.split_off(self.keys.len() / 2);
+            let copied = right.first()?.clone();
+            trace.push(TraceStep::SplitLeaf { keys, copied: copied.clone() });
+            return Some((copied, Self { keys: right, children: vec![], ..*self }))
+        }
+
+        let idx = self.child_for(key);
+        let (separator, right) = self.children[idx].insert_at(key, trace)?;
+        self.keys.insert(idx, separator);
+        self.children.insert(idx + 1, right);
+        if self.keys.len() <= self.max_keys() { return None }
+
+        // inner nodes split like a b-tree, the middle separator moves up
+        let keys = self.keys.clone();
+        let middle = self.keys.len() / 2;
+        let right_keys = self.keys.split_off(middle + 1);
+        let promoted = self.keys.pop()?;
+        let right_children = self.children.split_off(middle + 1);
+        trace.push(TraceStep::Split { keys, promoted: promoted.clone() });
+        Some((promoted, Self { keys: right_keys, children: right_children, ..*self }))
+    }
+
+    // removes a key from its leaf, borrowing from or merging with siblings when a node gets too small
+    pub fn delete(&mut self, key: &str) -> Vec<TraceStep> {
+        let mut trace = Vec::new();
+        self.delete_at(key.trim(), &mut trace);
+
+        // an empty root with one child is replaced by it, so the tree shrinks a level
+        if self.keys.is_empty() && self.children.len() == 1 {
+            *self = self.children.remove(0);
+            trace.push(TraceStep::Shrink);
+        }
+        trace
+    }
+
+    // separators of deleted keys are left in place, they still divide the keys correctly
+    fn delete_at(&mut self, key: &str, trace: &mut Vec<TraceStep>) {
+        trace.push(TraceStep::Visit { keys: self.keys.clone() });
+        if self.is_leaf() {
+            match self.find(key) {
+                Ok(idx) => {
+                    self.keys.remove(idx);
+                    trace.push(TraceStep::Remove { key: key.to_string(), keys: self.keys.clone() });
+                },
+                Err(_) => trace.push(TraceStep::NotFound { key: key.to_string() })
+            }
+            return
+        }
+
+        let idx = self.child_for(key);
+        self.children[idx].delete_at(key, trace);
+        self.rebalance(idx, trace);
+    }
+
+    // fixes a child with too few keys, first by borrowing from a sibling, otherwise by merging with one
+    fn rebalance(&mut self, idx: usize, trace: &mut Vec<TraceStep>) {
+        let min = self.children[idx].min_keys();
+        if self.children[idx].keys.len() >= min { return }
+        let leaf = self.children[idx].is_leaf();
+
+        if idx > 0 && self.children[idx - 1].keys.len() > min {
+            let (left, right) = self.children.split_at_mut(idx);
+            let (left, child) = (&mut left[idx - 1], &mut right[0]);
+            let Some(key) = left.keys.pop() else { return };
+            if leaf {
+                // the borrowed key is the new first key of the child, so it becomes the separator
+                child.keys.insert(0, key.clone());
+                self.keys[idx - 1] = key.clone();
+                trace.push(TraceStep::BorrowLeaf { from: Sibling::Left, key: key.clone(), separator: key });
+            } else {
+                let separator = std::mem::replace(&mut self.keys[idx - 1], key.clone());
+                child.keys.insert(0, separator.clone());
+                if let Some(moved) = left.children.pop() { child.children.insert(0, moved); }
+                trace.push(TraceStep::Borrow { from: Sibling::Left, key, separator });
+            }
+        } else if idx + 1 < self.children.len() && self.children[idx + 1].keys.len() > min {
+            let (left, right) = self.children.split_at_mut(idx + 1);
+            let (child, right) = (&mut left[idx], &mut right[0]);
+            let key = right.keys.remove(0);
+            if leaf {
+                // the sibling starts with a different key now, so the separator follows it
+                child.keys.push(key.clone());
+                let separator = right.keys.first().cloned().unwrap_or_default();
+                self.keys[idx] = separator.clone();
+                trace.push(TraceStep::BorrowLeaf { from: Sibling::Right, key, separator });
+            } else {
+                let separator = std::mem::replace(&mut self.keys[idx], key.clone());
+                child.keys.push(separator.clone());
+                child.children.push(right.children.remove(0));
+                trace.push(TraceStep::Borrow { from: Sibling::Right, key, separator });
+            }
+        } else if self.children.len() > 1 {
+            // merge with the left sibling if there is one, otherwise with the right
+            let left = if idx > 0 { idx - 1 } else { idx };
+            let right = self.children.remove(left + 1);
+            let separator = self.keys.remove(left);
+            let node = &mut self.children[left];
+            if leaf {
+                trace.push(TraceStep::MergeLeaves { left: node.keys.clone(), separator, right: right.keys.clone() });
+            } else {
+                trace.push(TraceStep::Merge { left: node.keys.clone(), separator: separator.clone(), right: right.keys.clone() });
+                node.keys.push(separator);
+            }
+            node.keys.extend(right.keys);
+            node.children.extend(right.children);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bminustree::tests::{follows_the_rules, scrambled};
+    use crate::bminustree::{BTree, TreeKind, TreeRoot};
+
+    // a tree with the scrambled keys inserted one at a time
+    fn inserted(order: usize) -> BPlusNode {
+        let mut root = BPlusNode::create(vec![], order, KeyOrder::Numeric);
+        scrambled().iter().for_each(|key| { root.insert(key); });
+        root
+    }
+
+    #[test]
+    fn b_plus_trees_follow_the_rules() { follows_the_rules(TreeKind::BPlus) }
+
+    #[test]
+    fn every_key_is_found_in_a_leaf() {
+        let root = BPlusNode::create((1 .. 30).map(|key| key.to_string()).collect(), 4, KeyOrder::Numeric);
+        let levels = std::iter::successors(Some(&root), |node| node.children.first()).count();
+        (1 .. 30).for_each(|key| {
+            let trace = root.search(&key.to_string());
+            assert!(matches!(trace.last(), Some(TraceStep::Found { .. })), "{key} was not found");
+            assert_eq!(trace.iter().filter(|step| matches!(step, TraceStep::Visit { .. })).count(), levels, "{key} was found above the leaves");
+        });
+        assert!(matches!(root.search("30").last(), Some(TraceStep::NotFound { .. })));
+    }
+
+    #[test]
+    fn linked_leaves_hold_every_key_in_order() {
+        (MIN_ORDER ..= 6).for_each(|order| {
+            let shapes = BTree::new(0, 0.0, 0.0, TreeRoot::BPlus(inserted(order))).shapes();
+            let first = shapes.iter().position(|shape| shape.pointers.iter().all(|(_, child)| child.is_none())).expect("a tree has a leaf");
+            let keys = std::iter::successors(Some(first), |&idx| shapes[idx].next)
+                .flat_map(|idx| shapes[idx].keys.iter().filter_map(|(_, key)| key.clone()))
+                .collect::<Vec<String>>();
+            assert_eq!(keys, (1 .. 61).map(|key| key.to_string()).collect::<Vec<String>>(), "order {order}");
+        });
+    }
+
+    #[test]
+    fn separators_are_copies_of_the_first_key_on_their_right() {
+        (MIN_ORDER ..= 6).map(|order| (order, inserted(order)))
+            .chain([(4, BPlusNode::create((1 .. 30).map(|key| key.to_string()).collect(), 4, KeyOrder::Numeric))])
+            .for_each(|(order, root)| root.nodes().into_iter().filter(|node| !node.is_leaf()).for_each(|node| {
+                node.keys.iter().zip(&node.children[1 ..]).for_each(|(separator, right)| {
+                    assert_eq!(*separator, right.first_key(), "order {order}");
+                    assert!(root.all_keys().contains(separator), "order {order}: {separator} is not in a leaf");
+                });
+            }));
+    }
+}
@@ -0,0 +1,322 @@
+use std::path::{Path, PathBuf};
+
+use native_dialog::FileDialog;
+
+use crate::{
+    bminustree::{self, KeyOrder, TreeKind, TreeOptions},
+    dsl, format,
+    layout::{self, Layout},
+    objects::Objects,
+    pdf::{self, PageSize, PdfOptions, PdfScale},
+    schema::{self, Dialect, Inheritance},
+    screenshot, sql_import, svg, validate
+};
+
+pub const USAGE: &str = "Usage:
+  entity_relationship_drawer [diagram.er | --new]
+      open the editor, with a diagram or an empty canvas
+
+  entity_relationship_drawer export <diagram.er> <output.png|svg|pdf> [--format png|svg|pdf] [--scale N]
+      [--page a4|a3|letter|legal] [--portrait] [--no-title-block]
+      draw a diagram to an image or document, the format comes from the extension unless given
+  entity_relationship_drawer validate <diagram.er> [--strict]
+      print problems, failing on errors, or on anything with --strict
+  entity_relationship_drawer convert <input> <output> [--from FORMAT] [--to FORMAT] [--no-positions]
+      [--dialect postgres|sqlite|mysql] [--inheritance class|single|concrete]
+      convert between er, text (.erd) and sql, the formats come from the extensions unless given
+  entity_relationship_drawer layout <diagram.er> <output.er> [--layout force|layered]
+      arrange everything that is not pinned
+  entity_relationship_drawer tree <keys.txt> <output.er> [--plus] [--order N] [--lexicographic]
+      [--delimiter D] [--limit N]
+      build a b-tree from a file of keys, adding it to the output diagram if it exists
+  entity_relationship_drawer screenshot <diagram.er>
+      ask where to save a png of a diagram, like older versions did
+
+Exit codes: 0 on success, 1 when a command fails or finds errors, 2 when it is used wrongly";
+
+const COMMANDS: [&str; 7] = ["help", "export", "validate", "convert", "layout", "tree", "screenshot"];
+
+// flags that are followed by a value
+const VALUE_FLAGS: [&str; 11] = ["--format", "--scale", "--page", "--from", "--to", "--dialect", "--inheritance", "--layout", "--order", "--delimiter", "--limit"];
+
+// why a command stopped, each has its own exit code
+#[derive(Debug)]
+pub enum Failure {
+    // the command was given the wrong arguments
+    Usage(String),
+    // the command ran but could not finish, or found errors
+    Failed(String)
+}
+
+impl Failure {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Failure::Usage(_) => 2,
+            Failure::Failed(_) => 1
+        }
+    }
+}
+
+impl std::fmt::Display for Failure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Failure::Usage(message) => write!(f, "{message}\nRun with --help to see every command"),
+            Failure::Failed(message) => write!(f, "{message}")
+        }
+    }
+}
+
+impl From<String> for Failure {
+    fn from(message: String) -> Self { Failure::Failed(message) }
+}
+
+// the arguments after the command name, split into paths and flags
+struct Args {
+    command: String,
+    positional: Vec<String>,
+    flags: Vec<(String, Option<String>)>
+}
+
+impl Args {
+    fn parse(command: &str, args: &[String]) -> Result<Self, Failure> {
+        let mut parsed = Args { command: command.into(), positional: vec![], flags: vec![] };
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            if !arg.starts_with("--") {
+                parsed.positional.push(arg.clone());
+            } else if VALUE_FLAGS.contains(&arg.as_str()) {
+                let value = args.next().ok_or_else(|| Failure::Usage(format!("{arg} needs a value")))?;
+                parsed.flags.push((arg.clone(), Some(value.clone())));
+            } else {
+                parsed.flags.push((arg.clone(), None));
+            }
+        }
+        Ok(parsed)
+    }
+
+    // fails on flags the command does not know and on a wrong number of paths
+    fn expect(&self, paths: usize, allowed: &[&str]) -> Result<(), Failure> {
+        if let Some((flag, _)) = self.flags.iter().find(|(flag, _)| !allowed.contains(&flag.as_str())) {
+            return Err(Failure::Usage(format!("{} does not take {flag}", self.command)))
+        }
+        if self.positional.len() != paths {
+            return Err(Failure::Usage(format!("{} takes {paths} path{}, got {}", self.command, if paths == 1 { "" } else { "s" }, self.positional.len())))
+        }
+        Ok(())
+    }
+
+    fn path(&self, idx: usize) -> PathBuf { PathBuf::from(&self.positional[idx]) }
+
+    fn has(&self, flag: &str) -> bool { self.flags.iter().any(|(name, _)| name == flag) }
+
+    fn value(&self, flag: &str) -> Option<&str> {
+        self.flags.iter().rev().find(|(name, _)| name == flag).and_then(|(_, value)| value.as_deref())
+    }
+
+    // a flag value picked from a list of names
+    fn choice<T: Copy>(&self, flag: &str, choices: &[(&str, T)], default: T) -> Result<T, Failure> {
+        let Some(value) = self.value(flag) else { return Ok(default) };
+        choices.iter().find(|(name, _)| name.eq_ignore_ascii_case(value)).map(|(_, choice)| *choice).ok_or_else(|| {
+            let names = choices.iter().map(|(name, _)| *name).collect::<Vec<&str>>().join(", ");
+            Failure::Usage(format!("{flag} has to be one of {names}, not {value}"))
+        })
+    }
+
+    fn number<T: std::str::FromStr>(&self, flag: &str) -> Result<Option<T>, Failure> {
+        self.value(flag).map(|value| value.parse::<T>().map_err(|_| Failure::Usage(format!("{flag} has to be a number, not {value}")))).transpose()
+    }
+}
+
+// every file format a diagram can be read from or written to
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Format {
+    Er,
+    Text,
+    Sql,
+    Png,
+    Svg,
+    Pdf
+}
+
+const FORMATS: [(&str, Format); 7] = [
+    ("er", Format::Er),
+    ("erd", Format::Text),
+    ("text", Format::Text),
+    ("sql", Format::Sql),
+    ("png", Format::Png),
+    ("svg", Format::Svg),
+    ("pdf", Format::Pdf)
+];
+
+impl Format {
+    // the format given with a flag, or the one matching the files extension
+    fn of(args: &Args, flag: &str, path: &Path) -> Result<Format, Failure> {
+        if args.value(flag).is_some() { return args.choice(flag, &FORMATS, Format::Er) }
+        let extension = path.extension().map(|extension| extension.to_string_lossy().to_lowercase()).unwrap_or_default();
+        FORMATS.iter().find(|(name, _)| *name == extension).map(|(_, format)| *format)
+            .ok_or_else(|| Failure::Usage(format!("Cannot tell the format of {} from its extension, give it with {flag}", path.display())))
+    }
+}
+
+// runs a command, none if the arguments are not a command so the editor should open instead
+pub fn run(args: &[String]) -> Option<Result<(), Failure>> {
+    let (command, rest) = args.split_first()?;
+    let command = match command.as_str() {
+        "--help" | "-h" => "help",
+        command if COMMANDS.contains(&command) => command,
+        _ => return None
+    };
+    Some(Args::parse(command, rest).and_then(|args| match args.command.as_str() {
+        "help" => {
+            println!("{USAGE}");
+            Ok(())
+        },
+        "export" => export(&args),
+        "validate" => check(&args),
+        "convert" => convert(&args),
+        "layout" => arrange(&args),
+        "tree" => tree(&args),
+        "screenshot" => screenshot(&args),
+        command => Err(Failure::Usage(format!("Unknown command {command}")))
+    }))
+}
+
+// reads a diagram, stopping with the reason if it cannot be read
+fn load(path: &Path) -> Result<Objects, Failure> {
+    format::load(path).map_err(|err| Failure::Failed(err.to_string()))
+}
+
+fn read_to_string(path: &Path) -> Result<String, Failure> {
+    std::fs::read_to_string(path).map_err(|err| Failure::Failed(format!("Could not read {}: {err}", path.display())))
+}
+
+fn write(path: &Path, text: String) -> Result<(), Failure> {
+    std::fs::write(path, text).map_err(|err| Failure::Failed(format!("Could not write {}: {err}", path.display())))
+}
+
+fn export(args: &Args) -> Result<(), Failure> {
+    args.expect(2, &["--format", "--scale", "--page", "--portrait", "--no-title-block"])?;
+    let (path, output) = (args.path(0), args.path(1));
+    let format = Format::of(args, "--format", &output)?;
+    let objects = load(&path)?;
+    draw(args, &objects, &output, format)
+}
+
+// the export older versions of the editor ran on themselves, which asks where the image goes
+fn screenshot(args: &Args) -> Result<(), Failure> {
+    args.expect(1, &[])?;
+    let objects = load(&args.path(0))?;
+    let output = FileDialog::new()
+        .set_location("~")
+        .add_filter("PNG", &["png"])
+        .show_save_single_file()
+        .map_err(|err| Failure::Failed(err.to_string()))?;
+    match output {
+        Some(output) => Ok(screenshot::screenshot(&objects, &output, 1.0)?),
+        None => Ok(())
+    }
+}
+
+// draws a diagram to an image or document
+fn draw(args: &Args, objects: &Objects, output: &Path, format: Format) -> Result<(), Failure> {
+    let scale = args.number::<f32>("--scale")?;
+    let pdf_only = ["--page", "--portrait", "--no-title-block"];
+    if format != Format::Pdf {
+        if let Some(flag) = pdf_only.iter().find(|flag| args.has(flag)) { return Err(Failure::Usage(format!("{flag} is only for pdf exports"))) }
+    }
+
+    match format {
+        Format::Png => Ok(screenshot::screenshot(objects, output, scale.unwrap_or(1.0))?),
+        Format::Svg if scale.is_some() => Err(Failure::Usage("svg exports cannot be scaled".into())),
+        Format::Svg => Ok(svg::export_svg(objects, output)?),
+        Format::Pdf => {
+            let pages = [("a4", PageSize::A4), ("a3", PageSize::A3), ("letter", PageSize::Letter), ("legal", PageSize::Legal)];
+            let options = PdfOptions {
+                page_size: args.choice("--page", &pages, PageSize::A4)?,
+                landscape: !args.has("--portrait"),
+                scale: scale.map(PdfScale::Fixed).unwrap_or(PdfScale::Fit),
+                title_block: !args.has("--no-title-block")
+            };
+            Ok(pdf::export_pdf(objects, output, &options)?)
+        },
+        _ => Err(Failure::Usage(format!("{} is not an image format, use convert for it", output.display())))
+    }
+}
+
+// prints every finding, failing on errors or on anything when strict
+fn check(args: &Args) -> Result<(), Failure> {
+    args.expect(1, &["--strict"])?;
+    let objects = load(&args.path(0))?;
+    let findings = validate::validate(&objects);
+    findings.iter().for_each(|finding| println!("{finding}"));
+    let failing = findings.iter().filter(|finding| args.has("--strict") || finding.severity == validate::Severity::Error).count();
+    match failing {
+        0 => Ok(()),
+        1 => Err(Failure::Failed("1 problem found".into())),
+        failing => Err(Failure::Failed(format!("{failing} problems found")))
+    }
+}
+
+fn convert(args: &Args) -> Result<(), Failure> {
+    args.expect(2, &["--from", "--to", "--no-positions", "--dialect", "--inheritance", "--scale", "--page", "--portrait", "--no-title-block"])?;
+    let (path, output) = (args.path(0), args.path(1));
+    let (from, to) = (Format::of(args, "--from", &path)?, Format::of(args, "--to", &output)?);
+
+    // read the diagram
+    let objects = match from {
+        Format::Er => load(&path)?,
+        Format::Text => dsl::parse(&read_to_string(&path)?)?,
+        Format::Sql => sql_import::import_sql(&read_to_string(&path)?)?,
+        _ => return Err(Failure::Usage(format!("Diagrams cannot be read from {}", path.display())))
+    };
+
+    // write it in the new format, printing how sql tables were mapped
+    match to {
+        Format::Er => Ok(format::save(&objects, &output)?),
+        Format::Text => write(&output, dsl::print(&objects, !args.has("--no-positions"))),
+        Format::Sql => {
+            let dialects = [("postgres", Dialect::Postgres), ("sqlite", Dialect::Sqlite), ("mysql", Dialect::MySql)];
+            let inheritances = [("class", Inheritance::ClassTable), ("single", Inheritance::SingleTable), ("concrete", Inheritance::ConcreteTable)];
+            let dialect = args.choice("--dialect", &dialects, Dialect::Postgres)?;
+            let schema = schema::map_objects(&objects, args.choice("--inheritance", &inheritances, Inheritance::ClassTable)?)?;
+            print!("{}", schema.report());
+            write(&output, schema.to_sql(dialect))
+        },
+        format => draw(args, &objects, &output, format)
+    }
+}
+
+// arranges everything that is not pinned
+fn arrange(args: &Args) -> Result<(), Failure> {
+    args.expect(2, &["--layout"])?;
+    let layouts = [("force", Layout::ForceDirected), ("layered", Layout::Layered)];
+    let layout = args.choice("--layout", &layouts, Layout::ForceDirected)?;
+
+    let mut objects = load(&args.path(0))?;
+    let pinned = objects.objects.iter().filter(|a| a.pinned).map(|a| a.id).collect::<Vec<u32>>();
+    layout::arrange(&mut objects, layout, &pinned);
+    Ok(format::save(&objects, &args.path(1))?)
+}
+
+// builds a tree from a file of keys, adding it to the output diagram if there is one
+fn tree(args: &Args) -> Result<(), Failure> {
+    args.expect(2, &["--plus", "--order", "--lexicographic", "--delimiter", "--limit"])?;
+    let (path, output) = (args.path(0), args.path(1));
+    let options = TreeOptions {
+        kind: if args.has("--plus") { TreeKind::BPlus } else { TreeKind::BMinus },
+        order: args.number("--order")?.unwrap_or(bminustree::DEFAULT_ORDER),
+        ordering: if args.has("--lexicographic") { KeyOrder::Lexicographic } else { KeyOrder::Numeric },
+        delimiter: args.value("--delimiter").map(|delimiter| match delimiter {
+            "\\n" => "\n".to_string(),
+            "\\t" => "\t".to_string(),
+            "whitespace" => String::new(),
+            delimiter => delimiter.to_string()
+        }).unwrap_or("\n".into()),
+        limit: args.number("--limit")?
+    };
+
+    let mut objects = if output.exists() { load(&output)? } else { Objects::default() };
+    let keys = options.keys(&read_to_string(&path)?)?;
+    objects.create_tree(keys, &options)?;
+    Ok(format::save(&objects, &output)?)
+}
@@ -21,7 +21,7 @@ pub fn draw_link(
     let b_to_a = f32::atan2(b.y - a.y, b.x - a.x);
 
     // line
-    let primary = vec![
+    let primary = [
         get_point_around_object(a, b_to_a, state),
         get_point_around_object(b, a_to_b, state)
     ];
@@ -120,7 +120,7 @@ pub fn draw_link(
         // draw
         ui.fonts(|fonts| {
             let font_id = FontId { size: 14.0, family: egui::FontFamily::Monospace };
-            let width = minmax.char_indices().into_iter().map(|(_, char)| fonts.glyph_width(&font_id, char)).sum::<f32>() / 2.0;
+            let width = minmax.char_indices().map(|(_, char)| fonts.glyph_width(&font_id, char)).sum::<f32>() / 2.0;
 
             shapes.push(Shape::text(    
                 fonts,        
@@ -138,25 +138,19 @@ pub fn draw_link(
 
     // draw unions if necessary
     let mut union_info: Option<(u32, bool, f32)> = None;
-    match &a.object_type {
-        ObjectType::Polymorph { poly } => {
-            union_info = Some((
-                a.id,
-                matches!(poly, Polymorph::Union),
-                a_to_b
-            ));
-        }
-        _ => {}
+    if let ObjectType::Polymorph { poly } = &a.object_type {
+        union_info = Some((
+            a.id,
+            matches!(poly, Polymorph::Union),
+            a_to_b
+        ));
     }
-    match &b.object_type {
-        ObjectType::Polymorph { poly } => {
-            union_info = Some((
-                b.id,
-                matches!(poly, Polymorph::Union),
-                b_to_a
-            ));
-        }
-        _ => {}
+    if let ObjectType::Polymorph { poly } = &b.object_type {
+        union_info = Some((
+            b.id,
+            matches!(poly, Polymorph::Union),
+            b_to_a
+        ));
     }
     if let Some((union_id, invert, direction)) = union_info {
         let is_first = union_ids.contains(&union_id);
@@ -255,7 +249,7 @@ pub fn get_point_around_object(
         ObjectType::RelationshipDependent { .. } => {
             let x_part = (-rad.abs() + (PI / 2.0)) / (PI / 2.0);
             let a_part = rad / (PI / 2.0);
-            let y_part = if a_part >= 0.0 { -(-a_part + 1.0).abs() + 1.0 } else { (-(a_part + 1.0).abs() + 1.0) * -1.0 };
+            let y_part = if a_part >= 0.0 { -(-a_part + 1.0).abs() + 1.0 } else { -(-(a_part + 1.0).abs() + 1.0) };
             vec2(
                 (object.width / 2.0) * x_part + center.x,
                 (object.width / 2.0) * y_part + center.y
@@ -15,36 +15,17 @@ pub fn draw_object(
         state.clip.height() / 2.0 + state.clip.min.y + object.y + state.scroll_offset.y
     );
 
-    // check if hovering
-    let is_selected = Some(object.id) == state.selected;
+    // check if hovering, the app selects and moves whatever was hovered last
+    let is_selected = state.selected.contains(&object.id);
     let is_hovering = (center.x - state.mouse_position.x).abs() <= object.width / 2.0 && (center.y - state.mouse_position.y).abs() <= object.height / 2.0;
-    let color = if is_hovering || is_selected { Color32::BLUE } else { Color32::BLACK };
-
-    if object.dragging && !state.dragging { object.dragging = false; }
-
-    // if me selected
-    if is_selected {
-        // if necessary, deselect
-        if state.click && !state.skip_click_check && !is_hovering { state.selected = None; }
-        // otherwise, if dragging and hover, mark dragging
-        else if state.dragging && is_hovering {
-            object.dragging = true;
-        }
-
-        // do drag
-        if object.dragging {
-            object.x = -state.clip.width() / 2.0 + state.mouse_position.x - state.scroll_offset.x;
-            object.y = -state.clip.height() / 2.0 - (object.height) + state.mouse_position.y - state.scroll_offset.y;
-        }
-    } 
-    // select me if not already selected but hovered
-    else if state.click && is_hovering { state.selected = Some(object.id); }
+    let color = if is_hovering || is_selected { Color32::BLUE } else if state.highlighted.contains(&object.id) { Color32::RED } else { Color32::BLACK };
+    if is_hovering { state.hovered = Some(object.id); }
 
     ui.fonts(|fonts| {
         let font_id = FontId { size: 14.0, family: egui::FontFamily::Monospace };
 
         // update widths and heights
-        let text_width = object.name.char_indices().into_iter().map(|(_, char)| fonts.glyph_width(&font_id, char)).sum::<f32>();
+        let text_width = object.name.char_indices().map(|(_, char)| fonts.glyph_width(&font_id, char)).sum::<f32>();
         let text_height = font_id.size;
         object.width = text_width + 20.0;
         object.height = text_height + 20.0;
@@ -206,7 +187,7 @@ pub fn draw_object(
                     crate::objects::Polymorph::Disjoint => "d",
                     crate::objects::Polymorph::Overlapping => "o",
                 };
-                let width = id.char_indices().into_iter().map(|(_, c)| fonts.glyph_width(&font_id, c)).sum::<f32>() + 15.0;
+                let width = id.char_indices().map(|(_, c)| fonts.glyph_width(&font_id, c)).sum::<f32>() + 15.0;
 
                 let mut result = Vec::new();
 
@@ -0,0 +1,82 @@
+use egui::{epaint::{CircleShape, RectShape}, pos2, vec2, Align2, Color32, FontId, Rect, Shape, Stroke, Ui};
+
+use crate::{bminustree::BTree, AppState};
+
+pub fn draw_tree(
+    tree: &BTree,
+    ui: &mut Ui,
+    state: &mut AppState
+) -> Vec<Shape> {
+    // canvas coordinates are relative to the center
+    let origin = pos2(
+        state.clip.width() / 2.0 + state.clip.min.x + state.scroll_offset.x,
+        state.clip.height() / 2.0 + state.clip.min.y + state.scroll_offset.y
+    ).to_vec2();
+    let mouse = state.mouse_position - origin;
+
+    // check if hovering, the app selects and moves whatever was hovered last
+    let nodes = tree.shapes();
+    let is_selected = state.selected.contains(&tree.id);
+    let is_hovering = nodes.iter().any(|node| node.rect.contains(mouse));
+    let color = if is_hovering || is_selected { Color32::BLUE } else if state.highlighted.contains(&tree.id) { Color32::RED } else { Color32::BLACK };
+    if is_hovering { state.hovered = Some(tree.id); }
+
+    let stroke = Stroke { width: 2.0, color };
+    let thin = Stroke { width: 1.0, color };
+
+    // nodes and keys pointed out by a playback
+    let marks = state.tree_marks.as_ref().filter(|(id, _)| *id == tree.id).map(|(_, marks)| marks);
+    let marked_node = |idx: usize| marks.is_some_and(|marks| marks.nodes.contains(&idx));
+    let marked_key = |key: &str| marks.is_some_and(|marks| marks.keys.iter().any(|a| a.trim() == key));
+
+    ui.fonts(|fonts| {
+        let font_id = FontId { size: 14.0, family: egui::FontFamily::Monospace };
+        let mut shapes = Vec::new();
+
+        // lines from pointers to the children they point at go under the nodes
+        nodes.iter().for_each(|node| node.pointers.iter().for_each(|(pointer, child)| {
+            let Some(child) = child.and_then(|child| nodes.get(child)) else { return };
+            shapes.push(Shape::LineSegment { points: [pointer.center() + origin, pos2(child.rect.center().x, child.rect.min.y) + origin], stroke: thin });
+        }));
+
+        nodes.iter().enumerate().for_each(|(idx, node)| {
+            shapes.push(Shape::Rect(RectShape {
+                rect: node.rect.translate(origin),
+                rounding: 0.0.into(),
+                fill: Color32::WHITE,
+                stroke: if marked_node(idx) { Stroke { width: 3.0, color: Color32::RED } } else { stroke },
+                fill_texture_id: egui::TextureId::default(),
+                uv: Rect { min: pos2(0.0, 0.0), max: pos2(1.0, 1.0) }
+            }));
+
+            // separate the key slots from the pointer cells
+            node.keys.iter().for_each(|(slot, key)| {
+                let slot = slot.translate(origin);
+                if key.as_deref().is_some_and(marked_key) {
+                    shapes.push(Shape::rect_filled(slot.shrink(1.0), 0.0, Color32::from_rgb(255, 220, 120)));
+                }
+                shapes.push(Shape::LineSegment { points: [slot.left_top(), slot.left_bottom()], stroke: thin });
+                shapes.push(Shape::LineSegment { points: [slot.right_top(), slot.right_bottom()], stroke: thin });
+                if let Some(key) = key {
+                    shapes.push(Shape::text(fonts, slot.center(), Align2::CENTER_CENTER, key, font_id.clone(), color));
+                }
+            });
+
+            // linked leaves point at the next one
+            if let Some(next) = node.next.and_then(|next| nodes.get(next)) {
+                let from = node.rect.right_center() + origin;
+                let to = next.rect.left_center() + origin;
+                shapes.push(Shape::LineSegment { points: [from, to], stroke: thin });
+                shapes.push(Shape::convex_polygon(vec![to, to + vec2(-7.0, -4.0), to + vec2(-7.0, 4.0)], color, Stroke::NONE));
+            }
+
+            // pointers that lead somewhere get a dot
+            node.pointers.iter().filter(|(_, child)| child.is_some()).for_each(|(pointer, _)| {
+                shapes.push(Shape::Circle(CircleShape { center: pointer.center() + origin, radius: 2.5, fill: color, stroke: Stroke::NONE }));
+            });
+        });
+
+        shapes
+    })
+}
+
@@ -0,0 +1,622 @@
+use std::{collections::HashMap, fmt::Write};
+
+use crate::{layout::{self, Layout}, objects::{CardType, Metadata, Object, ObjectType, Objects, Polymorph}};
+
+const KEYWORDS: [&str; 14] = [
+    "entity", "weak", "relationship", "attribute", "polymorph", "link", "at", "id", "derived",
+    "title", "author", "date", "revision", "union"
+];
+
+// the text format looks like this, positions and tags are optional
+//
+// title "University"
+// entity STUDENT at (0, 0) {
+//     attribute sid id
+//     attribute age derived
+// }
+// weak relationship HAS 1:N
+// polymorph STUDENT_poly disjoint
+// link STUDENT -- HAS "(0,N)"
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Word(String),
+    Text(String),
+    // kept as written so tags can be read as whole numbers
+    Number(String),
+    Symbol(char),
+    Arrow
+}
+
+#[derive(Clone, Debug)]
+struct Declaration {
+    object_type: ObjectType,
+    name: String,
+    tag: Option<u32>,
+    position: Option<(f32, f32)>,
+    children: Vec<Declaration>,
+    line: usize
+}
+
+#[derive(Clone, Debug)]
+enum Reference {
+    Name(String),
+    Tag(u32)
+}
+
+#[derive(Clone, Debug)]
+struct LinkStatement {
+    a: Reference,
+    b: Reference,
+    minmax: String,
+    line: usize
+}
+
+// parses diagram text into objects, placing anything without a position
+pub fn parse(text: &str) -> Result<Objects, String> {
+    let tokens = tokenize(text)?;
+    let mut parser = Parser { tokens, idx: 0 };
+    let mut declarations = Vec::new();
+    let mut links = Vec::new();
+    let mut metadata = Metadata::default();
+
+    while let Some(token) = parser.peek() {
+        let line = parser.line();
+        match token {
+            Token::Word(word) if word == "link" => {
+                parser.next();
+                let a = parser.reference()?;
+                parser.expect(&Token::Arrow, "--")?;
+                let b = parser.reference()?;
+                let minmax = match parser.peek() {
+                    Some(Token::Text(text)) => { let text = text.clone(); parser.next(); text },
+                    _ => String::new()
+                };
+                links.push(LinkStatement { a, b, minmax, line });
+            },
+            Token::Word(word) if ["title", "author", "date", "revision"].contains(&word.as_str()) => {
+                let field = word.clone();
+                parser.next();
+                let value = match parser.next() {
+                    Some((Token::Text(text), _)) => text,
+                    _ => return Err(format!("line {line}: expected quoted text after {field}"))
+                };
+                match field.as_str() {
+                    "title" => metadata.title = value,
+                    "author" => metadata.author = value,
+                    "date" => metadata.date = value,
+                    _ => metadata.revision = value
+                }
+            },
+            _ => declarations.push(parser.declaration()?)
+        }
+    }
+
+    build(declarations, links, metadata)
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    idx: usize
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> { self.tokens.get(self.idx).map(|(token, _)| token) }
+    fn line(&self) -> usize { self.tokens.get(self.idx).or(self.tokens.last()).map(|(_, line)| *line).unwrap_or(1) }
+
+    fn next(&mut self) -> Option<(Token, usize)> {
+        let token = self.tokens.get(self.idx).cloned();
+        self.idx += 1;
+        token
+    }
+
+    fn is_word(&self, word: &str) -> bool { matches!(self.peek(), Some(Token::Word(a)) if a == word) }
+
+    fn expect(&mut self, expected: &Token, description: &str) -> Result<(), String> {
+        let line = self.line();
+        match self.next() {
+            Some((token, _)) if token == *expected => Ok(()),
+            Some((token, _)) => Err(format!("line {line}: expected {description} but found {}", describe(&token))),
+            None => Err(format!("line {line}: expected {description} but the text ended"))
+        }
+    }
+
+    fn name(&mut self) -> Result<String, String> {
+        let line = self.line();
+        match self.next() {
+            // names always sit where no keyword could, so keywords are fine as names
+            Some((Token::Word(word), _)) => Ok(word),
+            Some((Token::Text(text), _)) => Ok(text),
+            Some((token, _)) => Err(format!("line {line}: expected a name but found {}", describe(&token))),
+            None => Err(format!("line {line}: expected a name but the text ended"))
+        }
+    }
+
+    fn number(&mut self) -> Result<f32, String> {
+        let line = self.line();
+        match self.next() {
+            Some((Token::Number(number), _)) => number.parse::<f32>().map_err(|_| format!("line {line}: invalid number {number}")),
+            Some((token, _)) => Err(format!("line {line}: expected a number but found {}", describe(&token))),
+            None => Err(format!("line {line}: expected a number but the text ended"))
+        }
+    }
+
+    // reads the number after a #, which has to be a whole number that fits an id
+    fn tag(&mut self) -> Result<u32, String> {
+        let line = self.line();
+        match self.next() {
+            Some((Token::Number(number), _)) => number.parse::<u32>().map_err(|_| format!("line {line}: tag #{number} is not a whole number from 0 to {}", u32::MAX)),
+            Some((token, _)) => Err(format!("line {line}: expected a tag number but found {}", describe(&token))),
+            None => Err(format!("line {line}: expected a tag number but the text ended"))
+        }
+    }
+
+    fn reference(&mut self) -> Result<Reference, String> {
+        if self.peek() == Some(&Token::Symbol('#')) {
+            self.next();
+            Ok(Reference::Tag(self.tag()?))
+        } else {
+            Ok(Reference::Name(self.name()?))
+        }
+    }
+
+    // parses any object declaration with its optional tag, position and attribute block
+    fn declaration(&mut self) -> Result<Declaration, String> {
+        let line = self.line();
+        let weak = self.is_word("weak");
+        if weak { self.next(); }
+
+        let kind = match self.next() {
+            Some((Token::Word(word), _)) => word,
+            Some((token, _)) => return Err(format!("line {line}: expected a declaration but found {}", describe(&token))),
+            None => return Err(format!("line {line}: expected a declaration but the text ended"))
+        };
+        let name = self.name()?;
+        let tag = if self.peek() == Some(&Token::Symbol('#')) {
+            self.next();
+            Some(self.tag()?)
+        } else { None };
+
+        let object_type = match (kind.as_str(), weak) {
+            ("entity", false) => ObjectType::Entity,
+            ("entity", true) => ObjectType::EntityDependent,
+            ("relationship", _) => {
+                let card = self.cardinality()?;
+                if weak { ObjectType::RelationshipDependent { card } } else { ObjectType::Relationship { card } }
+            },
+            ("attribute", false) => {
+                // flags can come in any order
+                let mut is_id = false;
+                let mut derived = false;
+                while self.is_word("id") || self.is_word("derived") {
+                    if self.is_word("id") { is_id = true } else { derived = true }
+                    self.next();
+                }
+                if derived { ObjectType::FunctionParameter { is_id } } else { ObjectType::Parameter { is_id } }
+            },
+            ("polymorph", false) => {
+                let poly = match self.next() {
+                    Some((Token::Word(word), _)) if word == "union" => Polymorph::Union,
+                    Some((Token::Word(word), _)) if word == "disjoint" => Polymorph::Disjoint,
+                    Some((Token::Word(word), _)) if word == "overlapping" => Polymorph::Overlapping,
+                    _ => return Err(format!("line {line}: expected union, disjoint or overlapping"))
+                };
+                ObjectType::Polymorph { poly }
+            },
+            (kind, true) => return Err(format!("line {line}: {kind} cannot be weak")),
+            (kind, false) => return Err(format!("line {line}: unknown declaration {kind}"))
+        };
+
+        // optional position
+        let position = if self.is_word("at") {
+            self.next();
+            self.expect(&Token::Symbol('('), "(")?;
+            let x = self.number()?;
+            self.expect(&Token::Symbol(','), ",")?;
+            let y = self.number()?;
+            self.expect(&Token::Symbol(')'), ")")?;
+            Some((x, y))
+        } else { None };
+
+        // optional attribute block
+        let mut children = Vec::new();
+        if self.peek() == Some(&Token::Symbol('{')) {
+            self.next();
+            while self.peek() != Some(&Token::Symbol('}')) {
+                if self.peek().is_none() { return Err(format!("line {line}: block is never closed")) }
+                if !self.is_word("attribute") { return Err(format!("line {}: only attributes can be declared inside a block", self.line())) }
+                children.push(self.declaration()?);
+            }
+            self.next();
+        }
+
+        Ok(Declaration { object_type, name, tag, position, children, line })
+    }
+
+    // reads cardinality written like 1:N
+    fn cardinality(&mut self) -> Result<CardType, String> {
+        let a = self.cardinality_side()?;
+        self.expect(&Token::Symbol(':'), ":")?;
+        let b = self.cardinality_side()?;
+        Ok(match (a, b) {
+            (false, false) => CardType::OneToOne,
+            (false, true) => CardType::OneToMany,
+            (true, false) => CardType::ManyToOne,
+            (true, true) => CardType::ManyToMany
+        })
+    }
+
+    // true if this side is many
+    fn cardinality_side(&mut self) -> Result<bool, String> {
+        let line = self.line();
+        match self.next() {
+            Some((Token::Number(number), _)) if number.parse::<f32>() == Ok(1.0) => Ok(false),
+            Some((Token::Word(word), _)) if word == "N" || word == "M" => Ok(true),
+            _ => Err(format!("line {line}: expected cardinality like 1:N"))
+        }
+    }
+}
+
+fn describe(token: &Token) -> String {
+    match token {
+        Token::Word(word) => format!("'{word}'"),
+        Token::Text(text) => format!("\"{text}\""),
+        Token::Number(number) => number.clone(),
+        Token::Symbol(c) => format!("'{c}'"),
+        Token::Arrow => "'--'".into()
+    }
+}
+
+fn tokenize(text: &str) -> Result<Vec<(Token, usize)>, String> {
+    let chars = text.chars().collect::<Vec<char>>();
+    let mut tokens = Vec::new();
+    let mut idx = 0;
+    let mut line = 1;
+
+    while idx < chars.len() {
+        let c = chars[idx];
+        match c {
+            '\n' => { line += 1; idx += 1; },
+            c if c.is_whitespace() => idx += 1,
+
+            // comments run to the end of the line
+            '/' if chars.get(idx + 1) == Some(&'/') => {
+                while idx < chars.len() && chars[idx] != '\n' { idx += 1; }
+            },
+
+            '-' if chars.get(idx + 1) == Some(&'-') => { tokens.push((Token::Arrow, line)); idx += 2; },
+
+            '"' => {
+                let start_line = line;
+                let mut text = String::new();
+                idx += 1;
+                loop {
+                    match chars.get(idx) {
+                        None => return Err(format!("line {start_line}: text is never closed")),
+                        Some('"') => { idx += 1; break },
+                        Some('\\') if idx + 1 < chars.len() => {
+                            text.push(match chars[idx + 1] { 'n' => '\n', other => other });
+                            idx += 2;
+                        },
+                        Some(c) => {
+                            if *c == '\n' { line += 1; }
+                            text.push(*c);
+                            idx += 1;
+                        }
+                    }
+                }
+                tokens.push((Token::Text(text), line));
+            },
+
+            c if c.is_ascii_digit() || (c == '-' && chars.get(idx + 1).is_some_and(|a| a.is_ascii_digit() || *a == '.')) || c == '.' => {
+                let start = idx;
+                idx += 1;
+                while idx < chars.len() && (chars[idx].is_ascii_digit() || chars[idx] == '.' || chars[idx] == 'e') { idx += 1; }
+                let number = chars[start .. idx].iter().collect::<String>();
+                if number.parse::<f32>().is_err() { return Err(format!("line {line}: invalid number {number}")) }
+                tokens.push((Token::Number(number), line));
+            },
+
+            c if c.is_alphabetic() || c == '_' => {
+                let start = idx;
+                while idx < chars.len() && (chars[idx].is_alphanumeric() || chars[idx] == '_') { idx += 1; }
+                tokens.push((Token::Word(chars[start .. idx].iter().collect()), line));
+            },
+
+            '{' | '}' | '(' | ')' | ',' | ':' | '#' => { tokens.push((Token::Symbol(c), line)); idx += 1; },
+
+            c => return Err(format!("line {line}: unexpected character '{c}'"))
+        }
+    }
+
+    Ok(tokens)
+}
+
+// turns parsed declarations into objects and links
+fn build(declarations: Vec<Declaration>, links: Vec<LinkStatement>, metadata: Metadata) -> Result<Objects, String> {
+    // flatten declarations keeping track of which attribute belongs to which owner
+    let mut flat: Vec<(Declaration, Option<usize>)> = Vec::new();
+    fn flatten(declaration: Declaration, parent: Option<usize>, flat: &mut Vec<(Declaration, Option<usize>)>) {
+        let idx = flat.len();
+        let children = declaration.children.clone();
+        flat.push((Declaration { children: vec![], ..declaration }, parent));
+        children.into_iter().for_each(|child| flatten(child, Some(idx), flat));
+    }
+    declarations.into_iter().for_each(|declaration| flatten(declaration, None, &mut flat));
+
+    // tagged objects keep their tag as id, everything else gets a fresh one
+    let mut ids = Vec::new();
+    let mut used = Vec::new();
+    for (declaration, _) in flat.iter() {
+        if let Some(tag) = declaration.tag {
+            if used.contains(&tag) { return Err(format!("line {}: tag #{tag} is used twice", declaration.line)) }
+            used.push(tag);
+        }
+    }
+    // ids after the biggest tag have to be left for the untagged objects
+    let untagged = flat.len() - used.len();
+    let mut next_id = match flat.iter().filter_map(|(declaration, _)| Some((declaration.tag?, declaration.line))).max() {
+        Some((max, line)) => max.checked_add(1).and_then(|next| next.checked_add(untagged as u32).map(|_| next))
+            .ok_or_else(|| format!("line {line}: tag #{max} is too big to leave ids for the other objects"))?,
+        None => 0
+    };
+    flat.iter().for_each(|(declaration, _)| ids.push(declaration.tag.unwrap_or_else(|| { next_id += 1; next_id - 1 })));
+
+    let mut objects = Objects { next_id, metadata, ..Default::default() };
+    flat.iter().zip(ids.iter()).for_each(|((declaration, _), id)| {
+        let (x, y) = declaration.position.unwrap_or_default();
+        objects.objects.push(Object { id: *id, x, y, name: declaration.name.clone(), object_type: declaration.object_type.clone(), ..Default::default() });
+    });
+
+    // explicit links come first so relationships and polymorphs keep their link order
+    let resolve = |reference: &Reference, line: usize| -> Result<u32, String> {
+        match reference {
+            Reference::Tag(tag) => if used.contains(tag) { Ok(*tag) } else { Err(format!("line {line}: no object is tagged #{tag}")) },
+            Reference::Name(name) => {
+                let found = flat.iter().zip(ids.iter()).filter(|((declaration, _), _)| declaration.name == *name).collect::<Vec<_>>();
+                match found.as_slice() {
+                    [(_, id)] => Ok(**id),
+                    [] => Err(format!("line {line}: no object is named {name}")),
+                    _ => Err(format!("line {line}: more than one object is named {name}, give them #tags to link them"))
+                }
+            }
+        }
+    };
+    for link in links.iter() {
+        let a = resolve(&link.a, link.line)?;
+        let b = resolve(&link.b, link.line)?;
+        objects.links.push(crate::objects::Link { a, b, minmax: link.minmax.clone() });
+    }
+    flat.iter().enumerate().for_each(|(idx, (_, parent))| {
+        if let Some(parent) = parent { objects.link(ids[*parent], ids[idx]); }
+    });
+
+    // lay out anything that was not given a position around what was
+    let placed = flat.iter().zip(ids.iter())
+        .filter(|((declaration, _), _)| declaration.position.is_some())
+        .map(|(_, id)| *id)
+        .collect::<Vec<u32>>();
+    if placed.len() < ids.len() {
+        layout::arrange(&mut objects, if placed.is_empty() { Layout::Layered } else { Layout::ForceDirected }, &placed);
+    }
+
+    Ok(objects)
+}
+
+fn is_attribute(object: &Object) -> bool {
+    matches!(object.object_type, ObjectType::Parameter { .. } | ObjectType::FunctionParameter { .. })
+}
+
+// prints objects as diagram text that parses back to the same diagram
+pub fn print(objects: &Objects, positions: bool) -> String {
+    let mut out = String::new();
+
+    // metadata
+    [("title", &objects.metadata.title), ("author", &objects.metadata.author), ("date", &objects.metadata.date), ("revision", &objects.metadata.revision)]
+        .iter()
+        .filter(|(_, value)| !value.is_empty())
+        .for_each(|(field, value)| { let _ = writeln!(out, "{field} {}", quote(value)); });
+    if !out.is_empty() { out.push('\n'); }
+
+    // attributes are nested under whatever their first link connects them to
+    let mut parents: HashMap<u32, u32> = HashMap::new();
+    let mut nested_links = Vec::new();
+    objects.objects.iter().filter(|object| is_attribute(object)).for_each(|attribute| {
+        let first = objects.links.iter().enumerate().find(|(_, link)| link.a == attribute.id || link.b == attribute.id);
+        let Some((idx, link)) = first else { return };
+        let parent = if link.a == attribute.id { link.b } else { link.a };
+        if link.minmax.is_empty() && objects.objects.iter().any(|object| object.id == parent) {
+            parents.insert(attribute.id, parent);
+            nested_links.push(idx);
+        }
+    });
+
+    // break any loops of attributes nesting each other by making one top level
+    let ids = parents.keys().copied().collect::<Vec<u32>>();
+    ids.iter().for_each(|id| {
+        let mut seen = vec![*id];
+        let mut current = *id;
+        while let Some(parent) = parents.get(&current).copied() {
+            if seen.contains(&parent) {
+                parents.remove(&current);
+                nested_links.retain(|idx| {
+                    let link = &objects.links[*idx];
+                    !((link.a == current && link.b == parent) || (link.b == current && link.a == parent))
+                });
+                break
+            }
+            seen.push(parent);
+            current = parent;
+        }
+    });
+
+    // names that are not unique need a tag to be referenced
+    let reference = |object: &Object| -> String {
+        let unique = objects.objects.iter().filter(|other| other.name == object.name).count() == 1;
+        if unique && !object.name.is_empty() { quote_name(&object.name) } else { format!("#{}", object.id) }
+    };
+    let needs_tag = |object: &Object| objects.objects.iter().filter(|other| other.name == object.name).count() != 1 || object.name.is_empty();
+
+    fn write_declaration(
+        out: &mut String,
+        objects: &Objects,
+        object: &Object,
+        depth: usize,
+        positions: bool,
+        parents: &HashMap<u32, u32>,
+        needs_tag: &dyn Fn(&Object) -> bool
+    ) {
+        let indent = "    ".repeat(depth);
+        let declaration = match &object.object_type {
+            ObjectType::Entity => "entity".to_string(),
+            ObjectType::EntityDependent => "weak entity".to_string(),
+            ObjectType::Relationship { .. } => "relationship".to_string(),
+            ObjectType::RelationshipDependent { .. } => "weak relationship".to_string(),
+            ObjectType::Parameter { .. } | ObjectType::FunctionParameter { .. } => "attribute".to_string(),
+            ObjectType::Polymorph { .. } => "polymorph".to_string()
+        };
+        let _ = write!(out, "{indent}{declaration} {}", quote_name(&object.name));
+        if needs_tag(object) { let _ = write!(out, " #{}", object.id); }
+
+        match &object.object_type {
+            ObjectType::Relationship { card } |
+            ObjectType::RelationshipDependent { card } => {
+                let _ = write!(out, " {}", match card {
+                    CardType::OneToOne => "1:1",
+                    CardType::OneToMany => "1:N",
+                    CardType::ManyToOne => "N:1",
+                    CardType::ManyToMany => "M:N"
+                });
+            },
+            ObjectType::Parameter { is_id: true } => out.push_str(" id"),
+            ObjectType::FunctionParameter { is_id } => out.push_str(if *is_id { " id derived" } else { " derived" }),
+            ObjectType::Polymorph { poly } => {
+                let _ = write!(out, " {}", match poly {
+                    Polymorph::Union => "union",
+                    Polymorph::Disjoint => "disjoint",
+                    Polymorph::Overlapping => "overlapping"
+                });
+            },
+            _ => {}
+        }
+        if positions { let _ = write!(out, " at ({}, {})", object.x, object.y); }
+
+        let children = objects.objects.iter().filter(|child| parents.get(&child.id) == Some(&object.id)).collect::<Vec<&Object>>();
+        if children.is_empty() {
+            out.push('\n');
+        } else {
+            out.push_str(" {\n");
+            children.iter().for_each(|child| write_declaration(out, objects, child, depth + 1, positions, parents, needs_tag));
+            let _ = writeln!(out, "{indent}}}");
+        }
+    }
+
+    objects.objects.iter()
+        .filter(|object| !parents.contains_key(&object.id))
+        .for_each(|object| write_declaration(&mut out, objects, object, 0, positions, &parents, &needs_tag));
+
+    // everything not expressed by nesting
+    let links = objects.links.iter().enumerate().filter(|(idx, _)| !nested_links.contains(idx)).collect::<Vec<_>>();
+    if !links.is_empty() { out.push('\n'); }
+    links.iter().for_each(|(_, link)| {
+        let a = objects.objects.iter().find(|object| object.id == link.a);
+        let b = objects.objects.iter().find(|object| object.id == link.b);
+        let (Some(a), Some(b)) = (a, b) else { return };
+        let _ = write!(out, "link {} -- {}", reference(a), reference(b));
+        if !link.minmax.is_empty() { let _ = write!(out, " {}", quote(&link.minmax)); }
+        out.push('\n');
+    });
+
+    out
+}
+
+// names are written bare when they would read back as the same name
+fn quote_name(name: &str) -> String {
+    let bare = name.chars().next().is_some_and(|c| c.is_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_alphanumeric() || c == '_')
+        && !KEYWORDS.contains(&name)
+        && !["disjoint", "overlapping"].contains(&name);
+    if bare { name.to_string() } else { quote(name) }
+}
+
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UNIVERSITY: &str = r#"title "University \"East\""
+author "A. Person"
+
+entity STUDENT at (10, -20) {
+    attribute sid id
+    attribute age derived
+    attribute address {
+        attribute street
+        attribute city
+    }
+}
+weak entity SECTION { attribute secno id derived }
+weak relationship HAS 1:N
+relationship "takes part" M:N
+polymorph STUDENT_poly overlapping
+entity COURSE #7
+entity COURSE #8
+attribute "" #9
+link STUDENT -- HAS "(0,N)"
+link HAS -- SECTION
+link STUDENT -- "takes part"
+link #7 -- "takes part" "(1,1)"
+link #8 -- #9
+link STUDENT -- STUDENT_poly
+"#;
+
+    #[test]
+    fn printing_parsed_text_is_stable() {
+        [true, false].iter().for_each(|positions| {
+            let printed = print(&parse(UNIVERSITY).unwrap(), *positions);
+            let reprinted = print(&parse(&printed).unwrap(), *positions);
+            assert_eq!(printed, reprinted);
+        });
+    }
+
+    #[test]
+    fn printed_text_keeps_the_diagram() {
+        let objects = parse(UNIVERSITY).unwrap();
+        let reparsed = parse(&print(&objects, true)).unwrap();
+        assert_eq!(reparsed.metadata.title, "University \"East\"");
+        assert_eq!(reparsed.objects.len(), objects.objects.len());
+        assert_eq!(reparsed.links.len(), objects.links.len());
+        objects.objects.iter().zip(reparsed.objects.iter()).for_each(|(a, b)| {
+            assert_eq!((&a.name, &a.object_type, a.x, a.y), (&b.name, &b.object_type, b.x, b.y));
+        });
+    }
+
+    #[test]
+    fn fractional_positions_are_kept() {
+        let mut objects = parse("entity A at (0, 0)").unwrap();
+        objects.objects[0].x = -0.000004371139;
+        objects.objects[0].y = 70.25;
+        let reparsed = parse(&print(&objects, true)).unwrap();
+        assert_eq!((reparsed.objects[0].x, reparsed.objects[0].y), (-0.000004371139, 70.25));
+    }
+
+    #[test]
+    fn tags_must_be_whole_numbers() {
+        assert!(parse("entity A #4294967294").is_ok());
+        ["entity A #-1", "entity A #1.5", "entity A #4294967296", "entity A #4294967295", "entity A #4294967294\nentity B", "entity A\nentity B #2\nlink A -- #1.5"].iter().for_each(|text| {
+            let err = parse(text).unwrap_err();
+            assert!(err.contains("tag #"), "{text}: {err}");
+        });
+        assert!(parse("entity A\nentity B\nlink A -- #-1").unwrap_err().starts_with("line 3:"));
+    }
+
+    #[test]
+    fn errors_name_their_line() {
+        let err = parse("entity A\nlink A -- B").unwrap_err();
+        assert!(err.starts_with("line 2:"), "{err}");
+        assert!(parse("entity A {\n    attribute a").unwrap_err().starts_with("line 1:"));
+    }
+}
@@ -0,0 +1,274 @@
+use std::{fmt, fs::File, io::{self, Write}, path::{Path, PathBuf}};
+
+use serde_json::Value;
+
+use crate::{bminustree::TreeRoot, objects::Objects};
+
+// the version of the file format written by this build, files without a version are version 0
+pub const VERSION: u64 = 1;
+
+// upgrades a file by one version
+type Migration = fn(&mut Value) -> Result<(), String>;
+
+// each step upgrades a file from the version at its index to the next one
+const MIGRATIONS: [Migration; VERSION as usize] = [
+    place_trees
+];
+
+// why a diagram could not be read, and where in the file
+#[derive(Clone, Debug, Default)]
+pub struct LoadError {
+    pub path: Option<PathBuf>,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    // where in the diagram the problem is, like objects[2].name
+    pub field: Option<String>,
+    pub message: String
+}
+
+impl LoadError {
+    fn new(message: impl Into<String>) -> Self {
+        Self { message: message.into(), ..Self::default() }
+    }
+
+    // keeps the position of a json error, and works out which field it is in from the text
+    fn json(err: serde_json::Error, text: &str) -> Self {
+        // serde adds the position to the message, it is shown separately
+        let message = err.to_string();
+        let message = message.split(" at line ").next().unwrap_or_default().to_string();
+        if err.line() == 0 { return Self::new(message) }
+        // a missing field is reported at the end of the object it is missing from, after the objects last key
+        let field = field_at(text, err.line(), err.column(), message.starts_with("missing field"));
+        Self { line: Some(err.line()), column: Some(err.column()), field: (!field.is_empty()).then_some(field), message, ..Self::default() }
+    }
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(path) = &self.path { write!(f, "{}: ", path.display())?; }
+        if let Some(line) = self.line { write!(f, "line {line}, column {}: ", self.column.unwrap_or_default())?; }
+        if let Some(field) = &self.field { write!(f, "in {field}: ")?; }
+        write!(f, "{}", self.message)
+    }
+}
+
+// reads a diagram file
+pub fn load(path: &Path) -> Result<Objects, LoadError> {
+    let at = |err: LoadError| LoadError { path: Some(path.to_path_buf()), ..err };
+    let text = std::fs::read_to_string(path).map_err(|err| at(LoadError::new(err.to_string())))?;
+    from_json(&text).map_err(at)
+}
+
+// reads a diagram, upgrading it first if it was saved by an older version
+pub fn from_json(text: &str) -> Result<Objects, LoadError> {
+    let mut value: Value = serde_json::from_str(text).map_err(|err| LoadError::json(err, text))?;
+    let Some(fields) = value.as_object_mut() else { return Err(LoadError::new("A diagram has to be a json object")) };
+    let version = match fields.remove("version") {
+        None => 0,
+        Some(version) => version.as_u64().ok_or_else(|| LoadError { field: Some("version".into()), ..LoadError::new(format!("{version} is not a version")) })?
+    };
+    if version > VERSION {
+        return Err(LoadError::new(format!("This diagram was saved by a newer version of the editor (format {version}, this one reads up to {VERSION})")))
+    }
+
+    // files that are already up to date are read straight from the text, so errors point at the right line
+    let mut objects: Objects = if version == VERSION {
+        serde_json::from_str(text).map_err(|err| LoadError::json(err, text))?
+    } else {
+        for (from, migrate) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+            migrate(&mut value).map_err(|err| LoadError::new(format!("Could not upgrade the diagram from format {from}: {err}")))?;
+        }
+        serde_json::from_value(value).map_err(|err| LoadError::new(format!("{err} (after upgrading from format {version})")))?
+    };
+
+    // ids already in the diagram are never handed out again, even if next_id is missing or behind
+    objects.version = None;
+    let used = objects.objects.iter().map(|a| a.id).chain(objects.trees.iter().map(|tree| tree.id)).max();
+    objects.next_id = objects.next_id.max(used.map(|id| id + 1).unwrap_or(0));
+    Ok(objects)
+}
+
+// writes a diagram with the current format version
+pub fn to_json(objects: &Objects) -> Result<String, String> {
+    let mut value = serde_json::to_value(objects).map_err(|err| err.to_string())?;
+    if let Some(fields) = value.as_object_mut() { fields.insert("version".into(), VERSION.into()); }
+    serde_json::to_string_pretty(&value).map_err(|err| err.to_string())
+}
+
+// writes a diagram to a file, the old file is only replaced once the new one is completely on disk
+pub fn save(objects: &Objects, path: &Path) -> Result<(), String> {
+    let text = to_json(objects)?;
+    write_atomic(path, text.as_bytes()).map_err(|err| format!("Could not write {}: {err}", path.display()))
+}
+
+// writes next to the target first, so a crash part way leaves the target untouched
+pub fn write_atomic(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    let Some(name) = path.file_name() else { return Err(io::Error::new(io::ErrorKind::InvalidInput, "not a file name")) };
+    let temp = path.with_file_name(format!(".{}.tmp", name.to_string_lossy()));
+    let written = File::create(&temp).and_then(|mut file| {
+        // keep the permissions of the file being replaced
+        if let Ok(metadata) = std::fs::metadata(path) { file.set_permissions(metadata.permissions())?; }
+        file.write_all(bytes)?;
+        file.sync_all()
+    }).and_then(|_| std::fs::rename(&temp, path));
+    if written.is_err() { let _ = std::fs::remove_file(&temp); }
+    written?;
+
+    // the rename itself is only durable once the folder is synced, which not every platform allows
+    if let Some(folder) = path.parent().filter(|folder| !folder.as_os_str().is_empty()) {
+        if let Ok(folder) = File::open(folder) { let _ = folder.sync_all(); }
+    }
+    Ok(())
+}
+
+// version 0 to 1: trees were saved as bare nodes without an id or position, they go below everything else
+fn place_trees(value: &mut Value) -> Result<(), String> {
+    let mut old_trees = match value.get_mut("trees").and_then(|trees| trees.as_array_mut()) {
+        Some(trees) if trees.iter().any(|tree| tree.get("keys").is_some()) => std::mem::take(trees),
+        _ => return Ok(())
+    };
+
+    // bare trees were sorted as text ignoring case, which is not the ordering nodes get when they have none
+    old_trees.iter_mut().for_each(sort_as_text);
+
+    let mut objects: Objects = serde_json::from_value(value.clone()).map_err(|err| err.to_string())?;
+    for tree in old_trees {
+        objects.add_tree(TreeRoot::BMinus(serde_json::from_value(tree).map_err(|err| format!("Could not read tree: {err}"))?));
+    }
+    *value = serde_json::to_value(objects).map_err(|err| err.to_string())?;
+    Ok(())
+}
+
+fn sort_as_text(node: &mut Value) {
+    let Some(fields) = node.as_object_mut() else { return };
+    fields.insert("ordering".into(), "Lexicographic".into());
+    if let Some(children) = fields.get_mut("children").and_then(|children| children.as_array_mut()) {
+        children.iter_mut().for_each(sort_as_text);
+    }
+}
+
+enum Scope {
+    Object(Option<String>),
+    Array(usize)
+}
+
+// the path to whatever is at a line and column of a json text, like objects[2].name, or to the object around it
+fn field_at(text: &str, line: usize, column: usize, whole_object: bool) -> String {
+    let offset = text.split_inclusive('\n').take(line - 1).map(str::len).sum::<usize>();
+    let offset = text[offset ..].char_indices().nth(column.saturating_sub(1)).map(|(idx, _)| offset + idx).unwrap_or(text.len());
+
+    let mut scopes = Vec::new();
+    let mut key_next = false;
+    let mut chars = text[.. offset].chars();
+    while let Some(char) = chars.next() {
+        match char {
+            '{' => {
+                scopes.push(Scope::Object(None));
+                key_next = true;
+            },
+            '[' => scopes.push(Scope::Array(0)),
+            '}' | ']' => { scopes.pop(); },
+            ',' => match scopes.last_mut() {
+                Some(Scope::Array(idx)) => *idx += 1,
+                Some(Scope::Object(_)) => key_next = true,
+                None => {}
+            },
+            '"' => {
+                // read the whole string, skipping escaped characters
+                let mut string = String::new();
+                while let Some(char) = chars.next() {
+                    match char {
+                        '\\' => { chars.next(); },
+                        '"' => break,
+                        _ => string.push(char)
+                    }
+                }
+                if let (true, Some(Scope::Object(key))) = (key_next, scopes.last_mut()) {
+                    *key = Some(string);
+                    key_next = false;
+                }
+            },
+            _ => {}
+        }
+    }
+
+    if let (true, Some(Scope::Object(key))) = (whole_object, scopes.last_mut()) { *key = None; }
+    scopes.iter().fold(String::new(), |path, scope| match scope {
+        Scope::Object(Some(key)) if path.is_empty() => key.clone(),
+        Scope::Object(Some(key)) => format!("{path}.{key}"),
+        Scope::Object(None) => path,
+        Scope::Array(idx) => format!("{path}[{idx}]")
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bminustree::KeyOrder;
+
+    // a diagram as the first version of the editor saved it, with a bare tree sorted as text
+    const VERSION_0: &str = r#"{
+        "objects": [{ "id": 0, "x": 0.0, "y": 0.0, "width": 0.0, "height": 0.0, "name": "A", "object_type": "Entity", "dragging": false }],
+        "links": [],
+        "trees": [{ "keys": ["2"], "children": [{ "keys": ["1", "10"], "children": [] }, { "keys": ["3"], "children": [] }] }],
+        "next_id": 1
+    }"#;
+
+    #[test]
+    fn bare_trees_keep_text_ordering() {
+        let objects = from_json(VERSION_0).unwrap();
+        let [tree] = objects.trees.as_slice() else { panic!("expected one tree") };
+        let TreeRoot::BMinus(root) = &tree.root else { panic!("expected a b-tree") };
+        assert_eq!(root.ordering, KeyOrder::Lexicographic);
+        assert!(root.children.iter().all(|child| child.ordering == KeyOrder::Lexicographic));
+        assert!(tree.root.check().is_empty());
+        assert_eq!(tree.root.all_keys(), ["1", "10", "2", "3"]);
+
+        // the tree is placed under the objects and gets the next id
+        assert_eq!(tree.id, 1);
+    }
+
+    #[test]
+    fn saved_diagrams_load_the_same() {
+        let objects = from_json(VERSION_0).unwrap();
+        let text = to_json(&objects).unwrap();
+        assert_eq!(to_json(&from_json(&text).unwrap()).unwrap(), text);
+    }
+
+    #[test]
+    fn errors_name_the_field_they_are_in() {
+        let field = |text: &str| from_json(text).unwrap_err().field;
+        let version = format!(r#""version": {VERSION}"#);
+        assert_eq!(field(&format!(r#"{{ {version}, "objects": [], "links": [{{ "a": 1, "b": -3 }}, {{ "a": 1, "b": 2 }}] }}"#)).as_deref(), Some("links[0].b"));
+        assert_eq!(field(&format!(r#"{{ {version}, "links": [{{ "a": 1, "b": 2, "minmax": 4 }}], "next_id": 1 }}"#)).as_deref(), Some("links[0].minmax"));
+        assert_eq!(field(&format!(r#"{{ {version}, "metadata": {{ "title": 1, "author": "a" }} }}"#)).as_deref(), Some("metadata.title"));
+        assert_eq!(field(&format!("{{ {version},\n\"next_id\": \"x\",\n\"links\": [] }}")).as_deref(), Some("next_id"));
+
+        // errors at a closing bracket are still inside what it closes
+        assert_eq!(field(&format!(r#"{{ {version}, "links": [{{ "a": 1, "b": 2 }},] }}"#)).as_deref(), Some("links[1]"));
+        assert_eq!(field(&format!(r#"{{ {version}, "trees": [{{ "id": 1, "x": 0, "y": 0, "root": {{ "keys": [] }} }}] }}"#)).as_deref(), Some("trees[0]"));
+    }
+
+    #[test]
+    fn missing_and_unknown_fields_are_errors() {
+        let err = |text: &str| from_json(text).unwrap_err();
+        let missing = err(r#"{ "version": 1, "objects": [{ "x": 0.0, "name": "A", "object_type": "Entity" }] }"#);
+        assert!(missing.message.contains("missing field `id`") && missing.field.as_deref() == Some("objects[0]"), "{missing}");
+        let missing = err(r#"{ "version": 1, "objects": [{ "id": 0, "name": "A" }] }"#);
+        assert!(missing.message.contains("missing field `object_type`"), "{missing}");
+        let unknown = err(r#"{ "version": 1, "objcts": [] }"#);
+        assert!(unknown.message.contains("unknown field `objcts`"), "{unknown}");
+    }
+
+    #[test]
+    fn ids_in_use_are_not_handed_out() {
+        let objects = from_json(r#"{ "version": 1, "objects": [{ "id": 4, "name": "A", "object_type": "Entity" }] }"#).unwrap();
+        assert_eq!(objects.next_id, 5);
+    }
+
+    #[test]
+    fn newer_versions_are_refused() {
+        let err = from_json(&format!(r#"{{ "version": {} }}"#, VERSION + 1)).unwrap_err();
+        assert!(err.message.contains("newer version"));
+    }
+}
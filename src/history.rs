@@ -0,0 +1,152 @@
+use crate::objects::Objects;
+
+// how many steps are kept before the oldest are dropped
+const MAX_STEPS: usize = 200;
+
+// no step has this id, so a diagram marked with it always counts as unsaved
+const NEVER_SAVED: u64 = u64::MAX;
+
+#[derive(Clone, Debug, Default)]
+pub struct History {
+    undo: Vec<Step>,
+    redo: Vec<Step>,
+    // the last id given to a step, and the id of the newest step when the diagram was saved, 0 being no steps at all
+    last_id: u64,
+    saved: u64
+}
+
+// the diagram as it was on the other side of an edit
+#[derive(Clone, Debug)]
+struct Step {
+    id: u64,
+    label: String,
+    objects: Objects,
+    merge: Option<String>
+}
+
+impl History {
+    // remembers the diagram from before an edit so it can be undone
+    pub fn push(&mut self, label: impl Into<String>, before: Objects) {
+        let id = self.next_id();
+        self.push_step(Step { id, label: label.into(), objects: before, merge: None });
+    }
+
+    // like push, but edits with the same key right after each other become one step
+    pub fn push_merged(&mut self, label: impl Into<String>, key: impl Into<String>, before: Objects) {
+        let key = key.into();
+        let id = self.next_id();
+        if self.redo.is_empty() {
+            if let Some(last) = self.undo.last_mut().filter(|last| last.merge.as_ref() == Some(&key)) {
+                // the merged step ends somewhere new, so it is no longer the one that was saved
+                last.label = label.into();
+                last.id = id;
+                return
+            }
+        }
+        self.push_step(Step { id, label: label.into(), objects: before, merge: Some(key) });
+    }
+
+    fn next_id(&mut self) -> u64 {
+        self.last_id += 1;
+        self.last_id
+    }
+
+    fn push_step(&mut self, step: Step) {
+        self.undo.push(step);
+        self.redo.clear();
+        if self.undo.len() > MAX_STEPS { self.undo.remove(0); }
+    }
+
+    // swaps the diagram with the one from before the last edit
+    pub fn undo(&mut self, objects: &mut Objects) -> bool {
+        let Some(mut step) = self.undo.pop() else { return false };
+        std::mem::swap(&mut step.objects, objects);
+        step.merge = None;
+        self.redo.push(step);
+        true
+    }
+
+    // swaps the diagram with the one from after the last undone edit
+    pub fn redo(&mut self, objects: &mut Objects) -> bool {
+        let Some(mut step) = self.redo.pop() else { return false };
+        std::mem::swap(&mut step.objects, objects);
+        self.undo.push(step);
+        true
+    }
+
+    // forgets everything, used when a different diagram is loaded
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.saved = 0;
+    }
+
+    // remembers that the diagram as it is now has been saved
+    pub fn mark_saved(&mut self) { self.saved = self.current(); }
+
+    // for diagrams that did not come from a file, like imports
+    pub fn mark_unsaved(&mut self) { self.saved = NEVER_SAVED; }
+
+    // whether the diagram changed since it was last saved or loaded
+    pub fn is_modified(&self) -> bool { self.current() != self.saved }
+
+    // the id of the newest edit, which changes whenever the diagram does
+    pub fn current(&self) -> u64 { self.undo.last().map(|step| step.id).unwrap_or(0) }
+
+    pub fn can_undo(&self) -> bool { !self.undo.is_empty() }
+    pub fn can_redo(&self) -> bool { !self.redo.is_empty() }
+
+    // labels of edits that can be undone, newest first
+    pub fn undo_labels(&self) -> impl Iterator<Item = &str> { self.undo.iter().rev().map(|step| step.label.as_str()) }
+
+    // labels of edits that can be redone, next first
+    pub fn redo_labels(&self) -> impl Iterator<Item = &str> { self.redo.iter().rev().map(|step| step.label.as_str()) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a diagram that differs from the empty one by its title
+    fn titled(title: &str) -> Objects {
+        let mut objects = Objects::default();
+        objects.metadata.title = title.into();
+        objects
+    }
+
+    #[test]
+    fn merged_edits_count_as_unsaved() {
+        let mut history = History::default();
+        assert!(!history.is_modified());
+
+        // typing a title is one step, but every key typed is a change to save
+        history.push_merged("Edit title", "metadata Title", titled(""));
+        assert!(history.is_modified());
+        history.mark_saved();
+        history.push_merged("Edit title", "metadata Title", titled("A"));
+        assert!(history.is_modified());
+        assert_eq!(history.undo_labels().count(), 1);
+
+        // undoing to before the save is a change too, redoing back to it is not
+        let mut objects = titled("AB");
+        history.undo(&mut objects);
+        assert!(history.is_modified());
+        history.redo(&mut objects);
+        assert!(history.is_modified());
+    }
+
+    #[test]
+    fn undo_back_to_the_save_is_unmodified() {
+        let mut history = History::default();
+        let mut objects = titled("A");
+        history.push("Edit title", titled(""));
+        history.mark_saved();
+        history.push("Edit title", titled("A"));
+        objects.metadata.title = "AB".into();
+        history.undo(&mut objects);
+        assert!(!history.is_modified());
+        assert_eq!(objects.metadata.title, "A");
+        history.redo(&mut objects);
+        assert!(history.is_modified());
+    }
+}
@@ -0,0 +1,398 @@
+use std::{collections::HashMap, f32::consts::PI};
+
+use egui::{vec2, Vec2};
+
+use crate::objects::{Object, ObjectType, Objects, Polymorph};
+
+// spacing between rows of the layered layout and between objects in a row
+const LAYER_SPACING: f32 = 200.0;
+const NODE_SPACING: f32 = 60.0;
+
+// preferred link length for the force directed layout
+const IDEAL_LENGTH: f32 = 160.0;
+const FORCE_ITERATIONS: usize = 300;
+
+// distance from an owner to its attributes
+const ATTRIBUTE_RADIUS: f32 = 90.0;
+const COMPOSITE_RADIUS: f32 = 80.0;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Layout {
+    #[default]
+    ForceDirected,
+    Layered
+}
+
+// moves every object that is not fixed, attributes always end up around their owner
+pub fn arrange(objects: &mut Objects, layout: Layout, fixed: &[u32]) {
+    let graph = Graph::new(objects);
+    let positions = match layout {
+        Layout::ForceDirected => force_directed(&graph, fixed),
+        Layout::Layered => layered(&graph, fixed)
+    };
+
+    graph.nodes.iter().zip(positions).for_each(|(id, position)| {
+        if fixed.contains(id) { return }
+        if let Some(object) = objects.objects.iter_mut().find(|object| object.id == *id) {
+            object.x = position.x.round();
+            object.y = position.y.round();
+        }
+    });
+
+    orbit_attributes(objects, &graph, fixed);
+}
+
+fn is_attribute(object: &Object) -> bool { matches!(object.object_type, ObjectType::Parameter { .. } | ObjectType::FunctionParameter { .. }) }
+fn is_entity(object: &Object) -> bool { matches!(object.object_type, ObjectType::Entity | ObjectType::EntityDependent) }
+
+// width of an object, guessed from its name if it has not been drawn yet
+fn width(object: &Object) -> f32 {
+    object.width.max(object.name.chars().count() as f32 * 8.4 + 20.0)
+}
+
+// the diagram without attributes, which are placed around their owners afterwards
+struct Graph {
+    // entities, relationships and polymorphs
+    nodes: Vec<u32>,
+    positions: Vec<Vec2>,
+    // room each node needs including its attributes
+    sizes: Vec<f32>,
+    links: Vec<(usize, usize)>,
+    // links pointing down the diagram, owners before relationships before other members and supertypes before subtypes
+    flows: Vec<(usize, usize)>,
+    // specializations only, these are kept pointing downward in the force layout
+    specializations: Vec<(usize, usize)>,
+    // relationships and the entities they join
+    relationships: Vec<(usize, Vec<usize>)>,
+    // attributes with what they are attached to, owners come before their attributes
+    attributes: Vec<(u32, u32)>
+}
+
+impl Graph {
+    fn new(objects: &Objects) -> Self {
+        let object = |id: u32| objects.objects.iter().find(|object| object.id == id);
+        let neighbors = |id: u32| objects.links.iter()
+            .filter_map(|link| if link.a == id { object(link.b) } else if link.b == id { object(link.a) } else { None })
+            .collect::<Vec<&Object>>();
+
+        let cores = objects.objects.iter().filter(|object| !is_attribute(object)).collect::<Vec<&Object>>();
+        let nodes = cores.iter().map(|object| object.id).collect::<Vec<u32>>();
+        let index = nodes.iter().enumerate().map(|(idx, id)| (*id, idx)).collect::<HashMap<u32, usize>>();
+
+        // attributes are attached to whatever reaches them first from an entity, relationship or polymorph
+        let mut attributes = Vec::new();
+        let mut frontier = nodes.clone();
+        while !frontier.is_empty() {
+            let mut next = Vec::new();
+            frontier.iter().for_each(|owner| {
+                neighbors(*owner).into_iter().filter(|a| is_attribute(a)).for_each(|a| {
+                    if attributes.iter().any(|(id, _)| *id == a.id) { return }
+                    attributes.push((a.id, *owner));
+                    next.push(a.id);
+                });
+            });
+            frontier = next;
+        }
+
+        // attributes make the room an owner needs bigger
+        let sizes = cores.iter().map(|object| {
+            let count = attributes.iter().filter(|(_, owner)| *owner == object.id).count();
+            if count == 0 { width(object) } else { width(object).max(2.0 * attribute_radius(object, count) + 80.0) }
+        }).collect::<Vec<f32>>();
+
+        let links = objects.links.iter()
+            .filter_map(|link| Some((*index.get(&link.a)?, *index.get(&link.b)?)))
+            .filter(|(a, b)| a != b)
+            .collect::<Vec<(usize, usize)>>();
+
+        let mut flows = Vec::new();
+        let mut specializations = Vec::new();
+        let mut relationships = Vec::new();
+        cores.iter().enumerate().for_each(|(idx, object)| {
+            let members = neighbors(object.id).into_iter().filter(|a| is_entity(a)).map(|a| index[&a.id]).collect::<Vec<usize>>();
+            let Some((first, rest)) = members.split_first() else { return };
+            match &object.object_type {
+                // the first entity sits above the relationship and the others below
+                ObjectType::Relationship { .. } | ObjectType::RelationshipDependent { .. } => {
+                    flows.push((*first, idx));
+                    rest.iter().filter(|member| *member != first).for_each(|member| flows.push((idx, *member)));
+                    relationships.push((idx, members.clone()));
+                },
+                // supertypes above, subtypes below
+                ObjectType::Polymorph { poly: Polymorph::Disjoint | Polymorph::Overlapping } => {
+                    flows.push((*first, idx));
+                    specializations.push((*first, idx));
+                    rest.iter().for_each(|member| { flows.push((idx, *member)); specializations.push((idx, *member)); });
+                },
+                // a union category is below the supertypes it is made from
+                ObjectType::Polymorph { poly: Polymorph::Union } => {
+                    flows.push((idx, *first));
+                    specializations.push((idx, *first));
+                    rest.iter().for_each(|member| { flows.push((*member, idx)); specializations.push((*member, idx)); });
+                },
+                _ => {}
+            }
+        });
+
+        Graph {
+            nodes,
+            positions: cores.iter().map(|object| vec2(object.x, object.y)).collect(),
+            sizes,
+            links,
+            flows,
+            specializations,
+            relationships,
+            attributes
+        }
+    }
+}
+
+fn attribute_radius(owner: &Object, count: usize) -> f32 {
+    // keep roughly 70 points of circumference per attribute
+    (width(owner) / 2.0 + 40.0).max(ATTRIBUTE_RADIUS).max(count as f32 * 70.0 / (2.0 * PI))
+}
+
+// spreads nodes apart while links pull them together, starting from where they are now
+fn force_directed(graph: &Graph, fixed: &[u32]) -> Vec<Vec2> {
+    let count = graph.nodes.len();
+    let movable = graph.nodes.iter().map(|id| !fixed.contains(id)).collect::<Vec<bool>>();
+    let mut positions = graph.positions.clone();
+
+    // objects sitting on top of each other are spread along a spiral so forces have a direction
+    (0 .. count).for_each(|idx| {
+        if !movable[idx] { return }
+        if (0 .. count).any(|other| other != idx && (positions[other] - positions[idx]).length() < 1.0 && (other < idx || !movable[other])) {
+            let angle = idx as f32 * 2.4;
+            positions[idx] += vec2(angle.cos(), angle.sin()) * (40.0 + idx as f32 * 12.0);
+        }
+    });
+
+    for iteration in 0 .. FORCE_ITERATIONS {
+        let temperature = IDEAL_LENGTH * (1.0 - iteration as f32 / FORCE_ITERATIONS as f32) + 1.0;
+        let mut moves = vec![Vec2::ZERO; count];
+        let ideal = |a: usize, b: usize| IDEAL_LENGTH * 0.5 + (graph.sizes[a] + graph.sizes[b]) / 2.0;
+
+        // nearby objects push each other away
+        (0 .. count).for_each(|a| (a + 1 .. count).for_each(|b| {
+            let delta = positions[a] - positions[b];
+            let distance = delta.length().max(1.0);
+            if distance > ideal(a, b) * 2.0 { return }
+            let force = ideal(a, b).powi(2) / distance;
+            let direction = delta / distance;
+            moves[a] += direction * force;
+            moves[b] -= direction * force;
+        }));
+
+        // links pull their ends together
+        graph.links.iter().for_each(|(a, b)| {
+            let delta = positions[*a] - positions[*b];
+            let distance = delta.length().max(1.0);
+            let force = distance.powi(2) / ideal(*a, *b);
+            let direction = delta / distance;
+            moves[*a] -= direction * force;
+            moves[*b] += direction * force;
+        });
+
+        // relationships are pulled to the middle of their entities
+        graph.relationships.iter().filter(|(_, members)| members.len() >= 2).for_each(|(idx, members)| {
+            let middle = members.iter().map(|member| positions[*member]).fold(Vec2::ZERO, |a, b| a + b) / members.len() as f32;
+            moves[*idx] += (middle - positions[*idx]) * 2.0;
+        });
+
+        (0 .. count).filter(|idx| movable[*idx]).for_each(|idx| {
+            let length = moves[idx].length();
+            if length > 0.0 { positions[idx] += moves[idx] / length * length.min(temperature); }
+        });
+
+        // specializations point downward, anything above its supertype is moved below it
+        graph.specializations.iter().for_each(|(above, below)| {
+            let gap = positions[*below].y - positions[*above].y;
+            let wanted = IDEAL_LENGTH * 0.6;
+            if gap >= wanted { return }
+            match (movable[*above], movable[*below]) {
+                (true, true) => {
+                    positions[*above].y -= (wanted - gap) / 2.0;
+                    positions[*below].y += (wanted - gap) / 2.0;
+                },
+                (true, false) => positions[*above].y -= wanted - gap,
+                (false, true) => positions[*below].y += wanted - gap,
+                _ => {}
+            }
+        });
+    }
+
+    positions
+}
+
+// puts nodes in rows so links flow downward, then orders each row to keep links short
+fn layered(graph: &Graph, fixed: &[u32]) -> Vec<Vec2> {
+    let count = graph.nodes.len();
+
+    // drop links that would make a loop, found with a depth first search
+    let mut state = vec![0u8; count];
+    let mut flows = Vec::new();
+    fn visit(node: usize, graph: &Graph, state: &mut Vec<u8>, flows: &mut Vec<(usize, usize)>) {
+        state[node] = 1;
+        graph.flows.iter().filter(|(from, _)| *from == node).for_each(|(from, to)| {
+            match state[*to] {
+                0 => { flows.push((*from, *to)); visit(*to, graph, state, flows); },
+                2 => flows.push((*from, *to)),
+                _ => {}
+            }
+        });
+        state[node] = 2;
+    }
+    (0 .. count).for_each(|node| if state[node] == 0 { visit(node, graph, &mut state, &mut flows) });
+
+    // each node goes one row below everything above it
+    let mut ranks = vec![0usize; count];
+    let mut changed = true;
+    while changed {
+        changed = false;
+        flows.iter().for_each(|(from, to)| {
+            if ranks[*to] < ranks[*from] + 1 {
+                ranks[*to] = ranks[*from] + 1;
+                changed = true;
+            }
+        });
+    }
+
+    // nodes move down to sit right above what they point at so links stay short
+    changed = true;
+    while changed {
+        changed = false;
+        (0 .. count).for_each(|node| {
+            let Some(below) = flows.iter().filter(|(from, _)| *from == node).map(|(_, to)| ranks[*to]).min() else { return };
+            if below > ranks[node] + 1 {
+                ranks[node] = below - 1;
+                changed = true;
+            }
+        });
+    }
+
+    // nodes only joined by plain links share a row with what they are linked to
+    (0 .. count).filter(|node| !flows.iter().any(|(from, to)| from == node || to == node)).for_each(|node| {
+        if let Some(other) = graph.links.iter().find_map(|(a, b)| if *a == node { Some(*b) } else if *b == node { Some(*a) } else { None }) {
+            ranks[node] = ranks[other];
+        }
+    });
+
+    let rows = ranks.iter().max().map(|max| max + 1).unwrap_or(0);
+    let mut layers = vec![Vec::new(); rows];
+    (0 .. count).for_each(|node| layers[ranks[node]].push(node));
+
+    // reorder each row by the average position of its neighbors, sweeping down and up
+    let neighbors = |node: usize| graph.links.iter()
+        .filter_map(move |(a, b)| if *a == node { Some(*b) } else if *b == node { Some(*a) } else { None });
+    for sweep in 0 .. 8 {
+        let order = if sweep % 2 == 0 { (1 .. rows).collect::<Vec<usize>>() } else { (0 .. rows.saturating_sub(1)).rev().collect() };
+        for row in order {
+            let placement = layers.iter()
+                .flat_map(|layer| layer.iter().enumerate().map(|(idx, node)| (*node, (idx as f32 + 0.5) / layer.len() as f32)))
+                .collect::<HashMap<usize, f32>>();
+            let reference = if sweep % 2 == 0 { row - 1 } else { row + 1 };
+            let key = |node: usize| {
+                let near = neighbors(node).filter(|other| ranks[*other] == reference).map(|other| placement[&other]).collect::<Vec<f32>>();
+                if near.is_empty() { placement[&node] } else { near.iter().sum::<f32>() / near.len() as f32 }
+            };
+            layers[row].sort_by(|a, b| key(*a).total_cmp(&key(*b)));
+        }
+    }
+
+    // place rows centered, then slide nodes toward their neighbors without overlapping
+    let mut positions = vec![Vec2::ZERO; count];
+    layers.iter().enumerate().for_each(|(row, layer)| {
+        let total = layer.iter().map(|node| graph.sizes[*node]).sum::<f32>() + NODE_SPACING * layer.len().saturating_sub(1) as f32;
+        let mut x = -total / 2.0;
+        layer.iter().for_each(|node| {
+            positions[*node] = vec2(x + graph.sizes[*node] / 2.0, row as f32 * LAYER_SPACING);
+            x += graph.sizes[*node] + NODE_SPACING;
+        });
+    });
+    for sweep in 0 .. 4 {
+        let order = if sweep % 2 == 0 { (0 .. rows).collect::<Vec<usize>>() } else { (0 .. rows).rev().collect() };
+        for row in order {
+            let layer = &layers[row];
+            let wanted = layer.iter().map(|node| {
+                let near = neighbors(*node).filter(|other| ranks[*other] != row).map(|other| positions[other].x).collect::<Vec<f32>>();
+                if near.is_empty() { positions[*node].x } else { near.iter().sum::<f32>() / near.len() as f32 }
+            }).collect::<Vec<f32>>();
+
+            // keep order and spacing, going left to right then right to left
+            let mut xs = wanted.clone();
+            (1 .. layer.len()).for_each(|idx| {
+                let gap = (graph.sizes[layer[idx - 1]] + graph.sizes[layer[idx]]) / 2.0 + NODE_SPACING;
+                xs[idx] = xs[idx].max(xs[idx - 1] + gap);
+            });
+            let shift = xs.iter().zip(wanted.iter()).map(|(x, wanted)| x - wanted).sum::<f32>() / layer.len().max(1) as f32;
+            xs.iter_mut().for_each(|x| *x -= shift);
+            (1 .. layer.len()).for_each(|idx| {
+                let gap = (graph.sizes[layer[idx - 1]] + graph.sizes[layer[idx]]) / 2.0 + NODE_SPACING;
+                xs[idx] = xs[idx].max(xs[idx - 1] + gap);
+            });
+            layer.iter().zip(xs).for_each(|(node, x)| positions[*node].x = x);
+        }
+    }
+
+    // fixed nodes keep their place
+    graph.nodes.iter().enumerate().filter(|(_, id)| fixed.contains(id)).for_each(|(idx, _)| positions[idx] = graph.positions[idx]);
+    positions
+}
+
+// places attributes around their owner, away from the owners other links
+fn orbit_attributes(objects: &mut Objects, graph: &Graph, fixed: &[u32]) {
+    let owners = graph.attributes.iter().fold(Vec::new(), |mut owners: Vec<u32>, (_, owner)| {
+        if !owners.contains(owner) { owners.push(*owner); }
+        owners
+    });
+
+    owners.into_iter().for_each(|owner_id| {
+        let Some(owner) = objects.objects.iter().find(|object| object.id == owner_id).cloned() else { return };
+        let children = graph.attributes.iter()
+            .filter(|(id, owner)| *owner == owner_id && !fixed.contains(id))
+            .map(|(id, _)| *id)
+            .collect::<Vec<u32>>();
+        if children.is_empty() { return }
+
+        // directions already taken by links to things that are not being placed here
+        let taken = objects.links.iter()
+            .filter_map(|link| if link.a == owner_id { Some(link.b) } else if link.b == owner_id { Some(link.a) } else { None })
+            .filter(|other| !children.contains(other))
+            .filter_map(|other| objects.objects.iter().find(|object| object.id == other))
+            .map(|other| vec2(other.x - owner.x, other.y - owner.y))
+            .filter(|direction| direction.length() > 0.1)
+            .map(|direction| direction.angle())
+            .collect::<Vec<f32>>();
+
+        let total = children.len() as f32;
+        let angles = if is_attribute(&owner) {
+            // parts of a composite attribute fan out over half a circle facing away from its owner
+            let away = taken.iter().fold(Vec2::ZERO, |away, angle| away - Vec2::angled(*angle));
+            let start = if away.length() > 0.1 { away.angle() } else { -PI / 2.0 };
+            (0 .. children.len()).map(|idx| start + (idx as f32 - (total - 1.0) / 2.0) * PI / total).collect::<Vec<f32>>()
+        } else {
+            // spread evenly, turned so the attributes are as far from the taken directions as possible
+            let step = 2.0 * PI / total;
+            let spread = |offset: f32| (0 .. children.len()).map(|idx| offset + idx as f32 * step).collect::<Vec<f32>>();
+            let clearance = |angles: &[f32]| angles.iter()
+                .flat_map(|a| taken.iter().map(move |b| {
+                    let difference = (a - b).rem_euclid(2.0 * PI);
+                    difference.min(2.0 * PI - difference)
+                }))
+                .fold(PI, f32::min);
+            let best = (0 .. 36)
+                .map(|turn| -PI / 2.0 + turn as f32 / 36.0 * step)
+                .map(|offset| (offset, clearance(&spread(offset))))
+                .fold((-PI / 2.0, -1.0), |best, candidate| if candidate.1 > best.1 + 0.01 { candidate } else { best });
+            spread(best.0)
+        };
+
+        let radius = if is_attribute(&owner) { COMPOSITE_RADIUS } else { attribute_radius(&owner, children.len()) };
+        children.iter().zip(angles).for_each(|(id, angle)| {
+            if let Some(object) = objects.objects.iter_mut().find(|object| object.id == *id) {
+                object.x = (owner.x + angle.cos() * radius).round();
+                object.y = (owner.y + angle.sin() * radius * 0.7).round();
+            }
+        });
+    });
+}
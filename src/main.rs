@@ -1,24 +1,104 @@
-use std::{fs::File, io::Write, path::PathBuf, process::Command, str::FromStr};
+use std::{hash::{Hash, Hasher}, path::{Path, PathBuf}, time::Instant};
 
 use draw_lines::draw_link;
 use draw_object::draw_object;
-use egui::{pos2, Color32, Pos2, Rect, Visuals};
-use native_dialog::*;
+use draw_tree::draw_tree;
+use bminustree::{KeyOrder, TreeKind, TreeOptions, TreeRoot};
+use history::History;
+use layout::Layout;
+use align::Alignment;
+use egui::{pos2, vec2, Color32, Pos2, Rect, Shape, Stroke, Vec2, Visuals};
+use native_dialog::FileDialog;
 use objects::{CardType, Link, Object, ObjectType, Objects};
+use pdf::{PageSize, PdfOptions, PdfScale};
+use playback::{Marks, Playback};
+use schema::{Dialect, Inheritance, MappingOptions};
 
 pub mod draw_lines;
 pub mod draw_object;
+pub mod draw_tree;
 pub mod objects;
 pub mod screenshot;
 pub mod bminustree;
+pub mod bplustree;
+pub mod svg;
+pub mod pdf;
+pub mod schema;
+pub mod sql_import;
+pub mod dsl;
+pub mod history;
+pub mod validate;
+pub mod layout;
+pub mod playback;
+pub mod zoom;
+pub mod align;
+pub mod format;
+pub mod recovery;
+pub mod cli;
+
+// how many files are kept in the recent files menu, and where the list is stored between runs
+const MAX_RECENT: usize = 10;
+const RECENT_KEY: &str = "recent_files";
+
+// something that throws away the open diagram, which waits while there are unsaved edits
+pub enum Pending {
+    Quit,
+    // another diagram, with the file it was read from if it matches that file
+    Replace { objects: Objects, saved_to: Option<PathBuf>, saved: bool },
+    // a recovery copy, put back in the list if cancelled
+    Restore(recovery::Recovered)
+}
+
+// generated sql with its report, or why the diagram could not be mapped
+pub type Mapped = Result<(String, String), String>;
 
 pub struct App {
     pub objects: Objects,
     pub scroll_offset: Pos2,
-    pub selected: Option<u32>,
+    // how much bigger than normal the canvas is drawn
+    pub zoom: f32,
+    pub show_grid: bool,
+    pub snap_to_grid: bool,
+    pub grid_spacing: f32,
+    pub selected: Vec<u32>,
     pub saved_to: Option<PathBuf>,
+    // files opened or saved lately, newest first
+    pub recent: Vec<PathBuf>,
     pub search: String,
-    pub clip: Rect
+    pub clip: Rect,
+    pub pdf_export: Option<PdfOptions>,
+    pub sql_export: Option<MappingOptions>,
+    // the generated sql, with the options and a hash of the diagram it was made for
+    pub sql_cache: Option<(MappingOptions, u64, Mapped)>,
+    pub history: History,
+    pub show_history: bool,
+    pub show_problems: bool,
+    pub keep_pinned: bool,
+    pub tree_keys: Option<(String, TreeOptions)>,
+    pub tree_edit: Option<(u32, String, usize)>,
+    pub tree_key: String,
+    pub tree_trace: Vec<bminustree::TraceStep>,
+    pub playback: Option<Playback>,
+    // why the last file could not be opened, shown until dismissed
+    pub open_error: Option<format::LoadError>,
+    pub save_error: Option<String>,
+    // anything else that failed, with the title of the window saying so
+    pub error: Option<(&'static str, String)>,
+    // waiting on whether to save unsaved edits first, and the title last given to the window
+    pub pending: Option<Pending>,
+    pub title: String,
+    // recovery copies from earlier runs waiting to be restored or thrown away
+    pub recovered: Vec<recovery::Recovered>,
+    pub recovery: recovery::Recovery,
+    // the edit last copied to the recovery folder and when
+    pub autosaved: Option<u64>,
+    pub autosaved_at: Instant,
+    // the last thing copied, for pasting from the menu
+    pub clipboard: String,
+    // where a move started, the diagram before it, the object grabbed and whether to select it alone if the pointer never moves
+    pub moving: Option<(Pos2, Objects, u32, bool)>,
+    // where a selection rectangle started
+    pub selecting: Option<Pos2>
 }
 
 #[derive(Debug)]
@@ -26,30 +106,117 @@ pub struct AppState {
     pub clip: Rect,
     pub scroll_offset: Pos2,
     pub mouse_position: Pos2,
-    pub selected: Option<u32>,
-    pub click: bool,
-    pub delete: bool,
-    pub dragging: bool,
-    pub skip_click_check: bool
+    pub selected: Vec<u32>,
+    // the object or tree under the pointer, set while drawing
+    pub hovered: Option<u32>,
+    pub highlighted: Vec<u32>,
+    pub tree_marks: Option<(u32, Marks)>
 }
 
 impl App {
-    pub fn from_context(context: &eframe::CreationContext<'_>) -> Self {
+    pub fn from_context(context: &eframe::CreationContext<'_>, open: Option<PathBuf>) -> Self {
         // set visuals
         context.egui_ctx.set_visuals(Visuals::light());
 
-        let mut objects = Objects::default();
-        let content = std::fs::read_to_string("bminustreetest.txt").unwrap();
-        let content: Vec<String> = content.split("\n").map(|a| a.into()).collect();
-        let mut content: Vec<String> = content[0..11].into();
-        content.sort_by(|a, b| a.to_lowercase().cmp(&b.to_lowercase()));
-        objects.create_tree(content);
-        
+        // mark this editor as running before looking for work left by others
+        let recovery = recovery::Recovery::default();
+        recovery.keep_alive();
+
         // create objects
-        Self { objects, scroll_offset: Pos2::default(), selected: None, saved_to: None, search: String::new(), clip: Rect { min: Pos2::default(), max: Pos2::default() } }
+        let mut app = Self {
+            objects: Objects::default(),
+            scroll_offset: Pos2::default(),
+            zoom: 1.0,
+            show_grid: false,
+            snap_to_grid: false,
+            grid_spacing: 20.0,
+            selected: vec![],
+            saved_to: None,
+            recent: context.storage.and_then(|storage| eframe::get_value(storage, RECENT_KEY)).unwrap_or_default(),
+            search: String::new(),
+            clip: Rect { min: Pos2::default(), max: Pos2::default() },
+            pdf_export: None,
+            sql_export: None,
+            sql_cache: None,
+            history: History::default(),
+            show_history: false,
+            show_problems: false,
+            keep_pinned: true,
+            tree_keys: None,
+            tree_edit: None,
+            tree_key: String::new(),
+            tree_trace: vec![],
+            playback: None,
+            open_error: None,
+            save_error: None,
+            error: None,
+            pending: None,
+            title: String::new(),
+            recovered: recovery.pending(),
+            recovery,
+            autosaved: None,
+            autosaved_at: Instant::now(),
+            clipboard: String::new(),
+            moving: None,
+            selecting: None
+        };
+
+        // open the file given on the command line
+        if let Some(path) = open { app.open(&context.egui_ctx, path); }
+        app
+    }
+
+    // replaces the diagram with one from a file, a file that cannot be read is shown and dropped from the recent files
+    pub fn open(&mut self, ctx: &egui::Context, path: PathBuf) {
+        match format::load(&path) {
+            Ok(objects) => self.request(ctx, Pending::Replace { objects, saved_to: Some(path), saved: true }),
+            Err(err) => {
+                self.recent.retain(|recent| *recent != path);
+                self.open_error = Some(err);
+            }
+        }
+    }
+
+    // does something that throws away the diagram, asking first if it has unsaved edits
+    pub fn request(&mut self, ctx: &egui::Context, pending: Pending) {
+        if !self.history.is_modified() { return self.apply(ctx, pending) }
+        if let Some(Pending::Restore(recovered)) = self.pending.replace(pending) { self.recovered.push(recovered); }
     }
 
-    pub fn save_as(&mut self) {
+    fn apply(&mut self, ctx: &egui::Context, pending: Pending) {
+        match pending {
+            Pending::Quit => ctx.send_viewport_cmd(egui::ViewportCommand::Close),
+            Pending::Replace { objects, saved_to, saved } => self.replace(objects, saved_to, saved),
+
+            // a restored diagram is unsaved again, so it is copied again under this run
+            Pending::Restore(recovered) => {
+                let _ = std::fs::remove_file(&recovered.path);
+                self.replace(recovered.objects, recovered.document, false);
+                self.autosaved_at = Instant::now().checked_sub(recovery::AUTOSAVE_INTERVAL).unwrap_or(self.autosaved_at);
+            }
+        }
+    }
+
+    fn replace(&mut self, objects: Objects, saved_to: Option<PathBuf>, saved: bool) {
+        self.objects = objects;
+        self.selected.clear();
+        self.history.clear();
+        if !saved { self.history.mark_unsaved(); }
+        self.autosaved = None;
+        if let (true, Some(path)) = (saved, &saved_to) { self.remember(path); }
+        self.saved_to = saved_to;
+    }
+
+    // puts a file at the top of the recent files
+    pub fn remember(&mut self, path: &Path) {
+        let path = path.canonicalize().unwrap_or(path.to_path_buf());
+        self.recent.retain(|recent| *recent != path);
+        self.recent.insert(0, path);
+        self.recent.truncate(MAX_RECENT);
+    }
+
+    // asks where to save, returning whether it was saved
+    pub fn save_as(&mut self) -> bool {
         // get save location
         let path = FileDialog::new()
             .set_location("~")
@@ -58,24 +225,128 @@ impl App {
             .unwrap();
     
         // do save
-        if path.is_some() {
-            self.save(path.unwrap());
+        match path {
+            Some(path) => self.save(path),
+            None => false
         }
     }
 
-    pub fn save(&mut self, path: PathBuf) {
-        let to_save = serde_json::to_string(&self.objects);
-        let file = File::create(path.clone());
-        if file.is_ok() && to_save.is_ok() {
-            let _ = file.unwrap().write(to_save.unwrap().as_bytes());
-            self.saved_to = Some(path);
-        } else {
-            println!("Save error, file: {:?}, to_save: {:?}", file, to_save);
+    // saves to the file it was saved to last, or asks where to
+    pub fn save_current(&mut self) -> bool {
+        match self.saved_to.clone() {
+            Some(path) => self.save(path),
+            None => self.save_as()
+        }
+    }
+
+    // converts a point on the screen to canvas coordinates, which are relative to the canvas center
+    pub fn canvas_position(&self, screen: Pos2) -> Pos2 {
+        ((screen - self.clip.center() - self.scroll_offset.to_vec2()) / self.zoom).to_pos2()
+    }
+
+    // changes the zoom while keeping the canvas point under a point on the screen in place
+    pub fn zoom_around(&mut self, screen: Pos2, zoom: f32) {
+        let at = self.canvas_position(screen);
+        self.zoom = zoom.clamp(zoom::MIN_ZOOM, zoom::MAX_ZOOM);
+        self.scroll_offset = screen - self.clip.center().to_vec2() - at.to_vec2() * self.zoom;
+    }
+
+    // zooms and scrolls so an area of the canvas fills the screen
+    pub fn zoom_to(&mut self, bounds: Option<Rect>) {
+        let Some(bounds) = bounds else { return };
+        self.zoom = zoom::fit(bounds, self.clip);
+        self.scroll_offset = (-bounds.center().to_vec2() * self.zoom).to_pos2();
+    }
+
+    // removes everything selected, along with its links
+    pub fn delete_selected(&mut self, verb: &str) {
+        if self.selected.is_empty() { return }
+        self.history.push(format!("{verb} {}", describe(&self.objects, &self.selected)), self.objects.clone());
+        let selected = std::mem::take(&mut self.selected);
+        self.objects.objects.retain(|a| !selected.contains(&a.id));
+        self.objects.trees.retain(|a| !selected.contains(&a.id));
+        self.objects.links.retain(|a| !selected.contains(&a.a) && !selected.contains(&a.b));
+    }
+
+    // puts the selection on the clipboard as a diagram of its own, so it can be pasted into any file or window
+    pub fn copy(&mut self, ctx: &egui::Context) {
+        if self.selected.is_empty() { return }
+        match format::to_json(&self.objects.extract(&self.selected)) {
+            Ok(text) => {
+                ctx.output_mut(|output| output.copied_text = text.clone());
+                self.clipboard = text;
+            },
+            Err(err) => self.error = Some(("Could Not Copy", err))
+        }
+    }
+
+    // adds copied objects with fresh ids and selects them, centered on a point or next to where they were copied from
+    pub fn paste(&mut self, text: &str, at: Option<Vec2>) {
+        // anything that is not a copied diagram is ignored
+        let Ok(copied) = format::from_json(text) else { return };
+        let Some(bounds) = copied.bounds() else { return };
+        let offset = at.map(|at| at - bounds.center().to_vec2()).unwrap_or(vec2(20.0, 20.0));
+        let before = self.objects.clone();
+        self.selected = self.objects.paste(&copied, offset);
+        self.history.push(format!("Paste {}", describe(&self.objects, &self.selected)), before);
+    }
+
+    // copies the selection in place, slightly moved
+    pub fn duplicate(&mut self) {
+        if self.selected.is_empty() { return }
+        let before = self.objects.clone();
+        let copied = self.objects.extract(&self.selected);
+        self.selected = self.objects.paste(&copied, vec2(20.0, 20.0));
+        self.history.push(format!("Duplicate {}", describe(&self.objects, &self.selected)), before);
+    }
+
+    // saves the diagram, a failed save is shown and leaves the file as it was
+    pub fn save(&mut self, path: PathBuf) -> bool {
+        match format::save(&self.objects, &path) {
+            Ok(()) => {
+                // the work is safe in the file now, under whichever name it had before too
+                self.recovery.remove(self.saved_to.as_deref());
+                self.recovery.remove(Some(&path));
+                self.autosaved = None;
+                self.remember(&path);
+                self.saved_to = Some(path);
+                self.history.mark_saved();
+                true
+            },
+            Err(err) => {
+                self.save_error = Some(err);
+                false
+            }
         }
     }
+
+    // copies unsaved edits to the recovery folder every so often, so a crash loses little
+    pub fn autosave(&mut self, ctx: &egui::Context) {
+        let current = self.history.current();
+        if !self.history.is_modified() {
+            // edits were saved or undone, the copy is not needed anymore
+            if self.autosaved.take().is_some() { self.recovery.remove(self.saved_to.as_deref()); }
+            return
+        }
+        if self.autosaved == Some(current) { return }
+
+        // wait until a while after the last copy, waking up then if nothing else happens
+        let since = self.autosaved_at.elapsed();
+        if since < recovery::AUTOSAVE_INTERVAL {
+            ctx.request_repaint_after(recovery::AUTOSAVE_INTERVAL - since);
+            return
+        }
+        if let Err(err) = self.recovery.write(self.saved_to.as_deref(), &self.objects) { self.error = Some(("Could Not Autosave", err)); }
+        self.autosaved = Some(current);
+        self.autosaved_at = Instant::now();
+    }
 }
 
 impl eframe::App for App {
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        eframe::set_value(storage, RECENT_KEY, &self.recent);
+    }
+
     fn update(&mut self, ctx: &egui::Context, _: &mut eframe::Frame) {
         // create top bar
         egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
@@ -91,210 +362,1078 @@ impl eframe::App for App {
                             .unwrap();
 
                         // do open
-                        if path.is_some() {
-                            self.objects = serde_json::from_str(std::fs::read_to_string(path.clone().unwrap()).unwrap().as_str()).unwrap();
-                            self.saved_to = Some(path.unwrap());
+                        if let Some(path) = path { self.open(ctx, path); }
+                    }
+
+                    // create recent files menu
+                    ui.menu_button("Recent Files", |ui| {
+                        if self.recent.is_empty() { ui.label("No recent files"); }
+                        let mut open = None;
+                        self.recent.iter().for_each(|path| {
+                            let name = path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+                            if ui.button(name).on_hover_text(path.display().to_string()).clicked() { open = Some(path.clone()); }
+                        });
+                        if !self.recent.is_empty() {
+                            ui.separator();
+                            if ui.button("Clear Recent Files").clicked() { self.recent.clear(); ui.close_menu(); }
                         }
+                        if let Some(path) = open {
+                            self.open(ctx, path);
+                            ui.close_menu();
+                        }
+                    });
+
+                    // create import button
+                    if ui.button("Import SQL").clicked() {
+                        // get import path
+                        let path = FileDialog::new()
+                            .set_location("~")
+                            .add_filter("SQL", &["sql"])
+                            .show_open_single_file()
+                            .unwrap();
+
+                        // build a new diagram from the tables
+                        if let Some(path) = path {
+                            match std::fs::read_to_string(&path).map_err(|err| err.to_string()).and_then(|sql| sql_import::import_sql(&sql)) {
+                                Ok(objects) => self.request(ctx, Pending::Replace { objects, saved_to: None, saved: false }),
+                                Err(err) => self.error = Some(("Could Not Import", err))
+                            }
+                        }
+                        ui.close_menu();
+                    }
+
+                    // create key import button, the keys are shown before the tree is built
+                    if ui.button("Import Keys").clicked() {
+                        match open_keys_file() {
+                            Ok(Some(text)) => {
+                                let options = self.tree_keys.take().map(|(_, options)| options).unwrap_or_default();
+                                self.tree_keys = Some((text, options));
+                            },
+                            Ok(None) => {},
+                            Err(err) => self.error = Some(("Could Not Import", err))
+                        }
+                        ui.close_menu();
+                    }
+
+                    // create text import button
+                    if ui.button("Import Text").clicked() {
+                        // get import path
+                        let path = FileDialog::new()
+                            .set_location("~")
+                            .add_filter("Entity Relationship Text", &["erd"])
+                            .show_open_single_file()
+                            .unwrap();
+
+                        // parse the text into a new diagram
+                        if let Some(path) = path {
+                            match std::fs::read_to_string(&path).map_err(|err| err.to_string()).and_then(|text| dsl::parse(&text)) {
+                                Ok(objects) => self.request(ctx, Pending::Replace { objects, saved_to: None, saved: false }),
+                                Err(err) => self.error = Some(("Could Not Import", err))
+                            }
+                        }
+                        ui.close_menu();
                     }
 
                     // create save button
-                    if ui.button("Save").clicked() { if self.saved_to.is_some() { self.save(self.saved_to.clone().unwrap()) } else { self.save_as() }; ui.close_menu(); }
+                    if ui.button("Save").clicked() { self.save_current(); ui.close_menu(); }
                     if ui.button("Save As").clicked() { self.save_as(); ui.close_menu(); }
 
-                    // create export button
+                    // create export buttons
                     if ui.button("Export").clicked() {
-                        // make sure saved
-                        if self.saved_to.is_none() { self.save_as() }
-                        if self.saved_to.is_none() { return }
-
-                        // take screen shot
-                        let args = std::env::args().collect::<Vec<String>>();
-                        let start_path = args.first().expect("Rules broke");
-                        let output = Command::new(start_path)
-                            .args(["screenshot", self.saved_to.clone().unwrap().to_str().unwrap()])
-                            .output()
-                            .expect("Screen shot failed!");
-                        println!("Export output: {:?}", output);
+                        // get export location
+                        let path = FileDialog::new()
+                            .set_location("~")
+                            .add_filter("PNG", &["png"])
+                            .show_save_single_file()
+                            .unwrap();
+
+                        // render at the screens scale so exports look the same as the editor
+                        if let Some(path) = path {
+                            if let Err(err) = screenshot::screenshot(&self.objects, &path, ctx.pixels_per_point()) {
+                                self.error = Some(("Could Not Export", err));
+                            }
+                        }
+
+                        ui.close_menu();
+                    }
+                    if ui.button("Export SVG").clicked() {
+                        // get export location
+                        let path = FileDialog::new()
+                            .set_location("~")
+                            .add_filter("SVG", &["svg"])
+                            .show_save_single_file()
+                            .unwrap();
+
+                        if let Some(path) = path {
+                            if let Err(err) = svg::export_svg(&self.objects, &path) {
+                                self.error = Some(("Could Not Export", err));
+                            }
+                        }
+
+                        ui.close_menu();
+                    }
+                    if ui.button("Export Text").clicked() {
+                        // get export location
+                        let path = FileDialog::new()
+                            .set_location("~")
+                            .add_filter("Entity Relationship Text", &["erd"])
+                            .show_save_single_file()
+                            .unwrap();
+
+                        if let Some(path) = path {
+                            if let Err(err) = std::fs::write(&path, dsl::print(&self.objects, true)) {
+                                self.error = Some(("Could Not Export", err.to_string()));
+                            }
+                        }
+
+                        ui.close_menu();
+                    }
+                    if ui.button("Export PDF").clicked() {
+                        // open the pdf options window
+                        if self.pdf_export.is_none() { self.pdf_export = Some(PdfOptions::default()); }
+                        ui.close_menu();
+                    }
+                    if ui.button("Generate SQL").clicked() {
+                        // open the sql window
+                        if self.sql_export.is_none() { self.sql_export = Some(MappingOptions::default()); }
+                        ui.close_menu();
+                    }
+                });
+                ui.menu_button("Edit", |ui| {
+                    if ui.add_enabled(self.history.can_undo(), egui::Button::new("Undo")).clicked() {
+                        self.history.undo(&mut self.objects);
+                        ui.close_menu();
+                    }
+                    if ui.add_enabled(self.history.can_redo(), egui::Button::new("Redo")).clicked() {
+                        self.history.redo(&mut self.objects);
+                        ui.close_menu();
+                    }
+                    ui.separator();
+                    if ui.add_enabled(!self.selected.is_empty(), egui::Button::new("Cut")).clicked() {
+                        self.copy(ui.ctx());
+                        self.delete_selected("Cut");
+                        ui.close_menu();
+                    }
+                    if ui.add_enabled(!self.selected.is_empty(), egui::Button::new("Copy")).clicked() {
+                        self.copy(ui.ctx());
+                        ui.close_menu();
+                    }
+                    if ui.add_enabled(!self.clipboard.is_empty(), egui::Button::new("Paste")).clicked() {
+                        self.paste(&self.clipboard.clone(), None);
+                        ui.close_menu();
+                    }
+                    if ui.add_enabled(!self.selected.is_empty(), egui::Button::new("Duplicate")).clicked() {
+                        self.duplicate();
+                        ui.close_menu();
+                    }
+                    ui.separator();
+                    if ui.button("History").clicked() {
+                        self.show_history = !self.show_history;
+                        ui.close_menu();
+                    }
+                    if ui.button("Validate").clicked() {
+                        self.show_problems = !self.show_problems;
+                        ui.close_menu();
+                    }
+                });
+                ui.menu_button("Arrange", |ui| {
+                    // pinned objects can be left where they are
+                    let pinned = if self.keep_pinned {
+                        self.objects.objects.iter().filter(|a| a.pinned).map(|a| a.id).collect::<Vec<u32>>()
+                    } else { vec![] };
+
+                    if ui.button("Force Directed").clicked() {
+                        self.history.push("Arrange force directed", self.objects.clone());
+                        layout::arrange(&mut self.objects, Layout::ForceDirected, &pinned);
+                        ui.close_menu();
+                    }
+                    if ui.button("Layered").clicked() {
+                        self.history.push("Arrange layered", self.objects.clone());
+                        layout::arrange(&mut self.objects, Layout::Layered, &pinned);
+                        ui.close_menu();
+                    }
+                    ui.checkbox(&mut self.keep_pinned, "Keep pinned objects in place");
 
+                    // line up or space out the selection
+                    ui.separator();
+                    Alignment::ALL.into_iter().for_each(|(alignment, label)| {
+                        if ui.add_enabled(self.selected.len() >= 2, egui::Button::new(label)).clicked() {
+                            self.history.push(label, self.objects.clone());
+                            align::align(&mut self.objects, &self.selected, alignment);
+                            ui.close_menu();
+                        }
+                    });
+                    [(true, "Distribute Horizontally"), (false, "Distribute Vertically")].into_iter().for_each(|(horizontal, label)| {
+                        if ui.add_enabled(self.selected.len() >= 3, egui::Button::new(label)).clicked() {
+                            self.history.push(label, self.objects.clone());
+                            align::distribute(&mut self.objects, &self.selected, horizontal);
+                            ui.close_menu();
+                        }
+                    });
+                });
+                ui.menu_button("View", |ui| {
+                    let center = self.clip.center();
+                    if ui.button("Zoom In").clicked() {
+                        self.zoom_around(center, self.zoom * 1.25);
+                        ui.close_menu();
+                    }
+                    if ui.button("Zoom Out").clicked() {
+                        self.zoom_around(center, self.zoom / 1.25);
+                        ui.close_menu();
+                    }
+                    if ui.button("Actual Size").clicked() {
+                        self.zoom_around(center, 1.0);
+                        ui.close_menu();
+                    }
+                    if ui.button("Zoom to Fit").clicked() {
+                        self.zoom_to(self.objects.bounds());
                         ui.close_menu();
                     }
+                    if ui.add_enabled(!self.selected.is_empty(), egui::Button::new("Zoom to Selection")).clicked() {
+                        self.zoom_to(self.objects.extract(&self.selected).bounds());
+                        ui.close_menu();
+                    }
+                    ui.separator();
+                    ui.checkbox(&mut self.show_grid, "Show Grid");
+                    ui.checkbox(&mut self.snap_to_grid, "Snap to Grid");
+                    ui.add(egui::DragValue::new(&mut self.grid_spacing).clamp_range(5.0 ..= 200.0).prefix("Grid spacing "));
                 });
                 if ui.button("Create").clicked() {
+                    self.history.push("Create entity", self.objects.clone());
                     let item = self.objects.add(objects::ObjectType::Entity, 0.0, 0.0);
-                    self.selected = Some(item.id);
+                    self.selected = vec![item.id];
+                    ui.close_menu();
+                }
+                if ui.button("Create Tree").clicked() {
+                    // open the key list window
+                    if self.tree_keys.is_none() { self.tree_keys = Some((String::new(), TreeOptions::default())); }
                     ui.close_menu();
                 }
                 ui.add_space(16.0);
+                ui.label(format!("{:.0}%", self.zoom * 100.0));
             });
         });
 
+        // closing with unsaved edits asks first
+        if ctx.input(|input| input.viewport().close_requested()) && self.history.is_modified() {
+            ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+            self.request(ctx, Pending::Quit);
+        }
+
+        self.autosave(ctx);
+
+        // the title shows the file name, with a star while there are unsaved edits
+        let name = self.saved_to.as_ref().and_then(|path| path.file_name()).map(|name| name.to_string_lossy().to_string()).unwrap_or("Untitled".into());
+        let title = format!("{name}{} - Entity Relationship Editor", if self.history.is_modified() { " *" } else { "" });
+        if title != self.title {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Title(title.clone()));
+            self.title = title;
+        }
+
         // read input
-        let (mouse_position, click, delete, dragging) = ctx.input(|input| {
+        let typing = ctx.wants_keyboard_input();
+        let (mouse_position, pressed, shift, delete, dragging) = ctx.input(|input| {
             let mouse_position = input.pointer.interact_pos().unwrap_or(pos2(0.0, 0.0));
 
-            // middle click drag
-            if input.pointer.is_decidedly_dragging() && input.pointer.button_down(egui::PointerButton::Secondary) {
-                // let drag_delta = input.pointer.delta();
-                // self.scroll_offset += drag_delta;
+            // undo and redo, text boxes handle these themselves while typing
+            if !typing && input.modifiers.command && input.key_pressed(egui::Key::Z) {
+                if input.modifiers.shift { self.history.redo(&mut self.objects); } else { self.history.undo(&mut self.objects); }
             }
 
-            if self.selected.is_none() && input.key_released(egui::Key::Tab) {
-                let item = self.objects.add(
-                    objects::ObjectType::Entity, 
-                    -self.clip.width() / 2.0 - 10.0 + mouse_position.x - self.scroll_offset.x, 
-                    -self.clip.height() / 2.0 - 30.0 + mouse_position.y - self.scroll_offset.y
-                );
-                self.selected = Some(item.id);
+            if self.selected.is_empty() && input.key_released(egui::Key::Tab) {
+                self.history.push("Create entity", self.objects.clone());
+                let at = self.canvas_position(mouse_position);
+                let item = self.objects.add(objects::ObjectType::Entity, at.x, at.y);
+                self.selected = vec![item.id];
             }
 
-            // reset scroll
-            if input.key_down(egui::Key::Space) {
+            // reset scroll and zoom
+            if !typing && input.key_down(egui::Key::Space) {
                 self.scroll_offset = Pos2::default();
+                self.zoom = 1.0;
             }
 
             // get pointer position
             (
                 mouse_position, 
-                input.pointer.button_clicked(egui::PointerButton::Primary),
-                input.key_down(egui::Key::Delete),
+                input.pointer.primary_pressed(),
+                input.modifiers.shift,
+                input.key_pressed(egui::Key::Delete),
                 input.pointer.button_down(egui::PointerButton::Primary)
             )
         });
 
-        
-        // if something is selected, draw selection edit window
+
+        // undo or redo may have removed selected objects
+        self.selected.retain(|id| self.objects.contains(*id));
+
+        // delete removes everything selected, along with its links
+        if delete && !typing { self.delete_selected("Delete"); }
+
+        // clipboard shortcuts, text boxes handle these themselves while typing
+        let (copy, cut, paste, duplicate) = ctx.input(|input| (
+            input.events.iter().any(|event| matches!(event, egui::Event::Copy)),
+            input.events.iter().any(|event| matches!(event, egui::Event::Cut)),
+            input.events.iter().find_map(|event| if let egui::Event::Paste(text) = event { Some(text.clone()) } else { None }),
+            input.modifiers.command && input.key_pressed(egui::Key::D)
+        ));
+        if !typing {
+            if copy { self.copy(ctx); }
+            if cut {
+                self.copy(ctx);
+                self.delete_selected("Cut");
+            }
+            // pasted objects are centered on the pointer when it is over the canvas
+            if let Some(text) = paste {
+                let at = self.clip.contains(mouse_position).then(|| self.canvas_position(mouse_position).to_vec2());
+                self.paste(&text, at);
+            }
+            if duplicate { self.duplicate(); }
+        }
+
+        // windows over the canvas take the pointer from it
         let mut skip_click_check = false;
-        if self.selected.is_some() {
-            let found = self.objects.objects.iter().find(|a| a.name.eq_ignore_ascii_case(self.search.as_str()) && Some(a.id) != self.selected && !self.search.is_empty()).cloned();
+
+        // if the history is open, list the recent edits
+        if self.show_history {
+            let mut open = true;
+            let mut undo = 0;
+            let mut redo = 0;
+
+            egui::Window::new("History")
+                .open(&mut open)
+                .show(ctx, |ui| {
+                    // if mouse contained, make sure to cancel click checks
+                    if ui.rect_contains_pointer(ui.clip_rect()) { skip_click_check = true; }
+
+                    ui.horizontal(|ui| {
+                        if ui.add_enabled(self.history.can_undo(), egui::Button::new("Undo")).clicked() { undo = 1; }
+                        if ui.add_enabled(self.history.can_redo(), egui::Button::new("Redo")).clicked() { redo = 1; }
+                    });
+
+                    // clicking an edit jumps back or forward to just after it
+                    egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                        let redo_labels = self.history.redo_labels().collect::<Vec<&str>>();
+                        redo_labels.iter().enumerate().rev().for_each(|(idx, label)| {
+                            if ui.add(egui::Label::new(egui::RichText::new(*label).weak()).sense(egui::Sense::click())).clicked() { redo = idx + 1; }
+                        });
+                        self.history.undo_labels().enumerate().for_each(|(idx, label)| {
+                            if ui.add(egui::Label::new(if idx == 0 { egui::RichText::new(label).strong() } else { egui::RichText::new(label) }).sense(egui::Sense::click())).clicked() { undo = idx; }
+                        });
+                    });
+                });
+
+            (0 .. undo).for_each(|_| { self.history.undo(&mut self.objects); });
+            (0 .. redo).for_each(|_| { self.history.redo(&mut self.objects); });
+            self.selected.retain(|id| self.objects.contains(*id));
+            if !open { self.show_history = false; }
+        }
+
+        // if a file could not be opened, say why
+        if let Some(err) = &self.open_error {
+            let mut open = true;
+            let mut close = false;
+
+            egui::Window::new("Could Not Open")
+                .open(&mut open)
+                .collapsible(false)
+                .show(ctx, |ui| {
+                    // if mouse contained, make sure to cancel click checks
+                    if ui.rect_contains_pointer(ui.clip_rect()) { skip_click_check = true; }
+
+                    if let Some(path) = &err.path { ui.label(format!("File: {}", path.display())); }
+                    if let Some(line) = err.line { ui.label(format!("Line {line}, column {}", err.column.unwrap_or_default())); }
+                    if let Some(field) = &err.field { ui.label(format!("Field: {field}")); }
+                    ui.colored_label(Color32::RED, &err.message);
+                    if ui.button("OK").clicked() { close = true; }
+                });
+
+            if !open || close { self.open_error = None; }
+        }
+
+        // if saving failed, say why, the file on disk is left as it was
+        if let Some(err) = &self.save_error {
+            let mut open = true;
+            let mut close = false;
+
+            egui::Window::new("Could Not Save")
+                .open(&mut open)
+                .collapsible(false)
+                .show(ctx, |ui| {
+                    // if mouse contained, make sure to cancel click checks
+                    if ui.rect_contains_pointer(ui.clip_rect()) { skip_click_check = true; }
+
+                    ui.colored_label(Color32::RED, err);
+                    ui.label("The file on disk has not been changed.");
+                    if ui.button("OK").clicked() { close = true; }
+                });
+
+            if !open || close { self.save_error = None; }
+        }
+
+        // if anything else failed, say what
+        if let Some((title, err)) = &self.error {
+            let mut open = true;
+            let mut close = false;
+
+            egui::Window::new(*title)
+                .open(&mut open)
+                .collapsible(false)
+                .show(ctx, |ui| {
+                    // if mouse contained, make sure to cancel click checks
+                    if ui.rect_contains_pointer(ui.clip_rect()) { skip_click_check = true; }
+
+                    ui.colored_label(Color32::RED, err);
+                    if ui.button("OK").clicked() { close = true; }
+                });
+
+            if !open || close { self.error = None; }
+        }
+
+        // if closing or replacing a diagram with unsaved edits, ask what to do with them
+        if let Some(pending) = &self.pending {
+            let mut choice = None;
+            let question = match pending {
+                Pending::Quit => "Save them before closing?",
+                _ => "Save them before the diagram is replaced?"
+            };
+
+            egui::Window::new("Unsaved Changes")
+                .collapsible(false)
+                .show(ctx, |ui| {
+                    // if mouse contained, make sure to cancel click checks
+                    if ui.rect_contains_pointer(ui.clip_rect()) { skip_click_check = true; }
+
+                    ui.label("This diagram has changes that have not been saved.");
+                    ui.label(question);
+                    ui.horizontal(|ui| {
+                        if ui.button("Save").clicked() { choice = Some("Save"); }
+                        if ui.button("Don't Save").clicked() { choice = Some("Discard"); }
+                        if ui.button("Cancel").clicked() { choice = Some("Cancel"); }
+                    });
+                });
+
+            // what was asked for goes ahead once nothing is left unsaved, a failed save keeps asking
+            let go_ahead = match choice {
+                Some("Save") => self.save_current(),
+                Some("Discard") => {
+                    self.recovery.remove(self.saved_to.as_deref());
+                    self.autosaved = None;
+                    self.history.mark_saved();
+                    true
+                },
+                Some(_) => {
+                    if let Some(Pending::Restore(recovered)) = self.pending.take() { self.recovered.push(recovered); }
+                    false
+                },
+                None => false
+            };
+            if let Some(pending) = self.pending.take_if(|_| go_ahead) { self.apply(ctx, pending); }
+        }
+
+        // offer to bring back work from a run that did not end cleanly
+        if !self.recovered.is_empty() {
+            let mut restore = None;
+            let mut discard = None;
+
+            egui::Window::new("Recover Unsaved Work")
+                .collapsible(false)
+                .show(ctx, |ui| {
+                    // if mouse contained, make sure to cancel click checks
+                    if ui.rect_contains_pointer(ui.clip_rect()) { skip_click_check = true; }
+
+                    ui.label("These diagrams had changes that were not saved. Restoring one replaces the open diagram.");
+                    self.recovered.iter().enumerate().for_each(|(idx, recovered)| {
+                        ui.separator();
+                        ui.strong(recovered.name());
+                        ui.label(format!("Autosaved {}", recovered.age()));
+                        recovered.differences.iter().for_each(|difference| { ui.label(format!("• {difference}")); });
+                        ui.horizontal(|ui| {
+                            if ui.button("Restore").clicked() { restore = Some(idx); }
+                            if ui.button("Discard").clicked() { discard = Some(idx); }
+                        });
+                    });
+                });
+
+            if let Some(idx) = restore {
+                let recovered = self.recovered.remove(idx);
+                self.request(ctx, Pending::Restore(recovered));
+            }
+            if let Some(idx) = discard {
+                let _ = std::fs::remove_file(&self.recovered.remove(idx).path);
+            }
+        }
+
+        // if validating, list the problems and highlight the objects involved
+        let mut highlighted = Vec::new();
+        if self.show_problems {
+            let mut open = true;
+            let findings = validate::validate(&self.objects);
+
+            egui::Window::new("Problems")
+                .open(&mut open)
+                .default_width(400.0)
+                .show(ctx, |ui| {
+                    // if mouse contained, make sure to cancel click checks
+                    if ui.rect_contains_pointer(ui.clip_rect()) { skip_click_check = true; }
+
+                    if findings.is_empty() { ui.label("No problems found"); }
+
+                    // clicking a problem selects the first object involved
+                    egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                        findings.iter().for_each(|finding| {
+                            let color = match finding.severity { validate::Severity::Error => Color32::RED, validate::Severity::Warning => Color32::from_rgb(200, 120, 0) };
+                            let text = egui::RichText::new(finding.to_string()).color(color);
+                            if ui.add(egui::Label::new(text).sense(egui::Sense::click())).clicked() {
+                                if let Some(id) = finding.objects.first() { self.selected = vec![*id]; }
+                            }
+                        });
+                    });
+                });
+
+            highlighted = findings.into_iter().flat_map(|finding| finding.objects).collect();
+            if !open { self.show_problems = false; }
+        }
+
+        // if exporting a pdf, draw the pdf options window
+        if let Some(options) = &mut self.pdf_export {
+            let mut open = true;
+            let mut export = false;
+            let mut metadata = self.objects.metadata.clone();
+            let mut edited = None;
+
+            egui::Window::new("Export PDF")
+                .open(&mut open)
+                .show(ctx, |ui| {
+                    // if mouse contained, make sure to cancel click checks
+                    if ui.rect_contains_pointer(ui.clip_rect()) { skip_click_check = true; }
+
+                    // page setup
+                    egui::ComboBox::from_label("Page Size")
+                        .selected_text(format!("{:?}", options.page_size))
+                        .show_ui(ui, |ui| {
+                            // yes I know doing this twice is kinda hacky
+                            if ui.rect_contains_pointer(ui.clip_rect()) { skip_click_check = true; }
+
+                            ui.selectable_value(&mut options.page_size, PageSize::A4, "A4");
+                            ui.selectable_value(&mut options.page_size, PageSize::A3, "A3");
+                            ui.selectable_value(&mut options.page_size, PageSize::Letter, "Letter");
+                            ui.selectable_value(&mut options.page_size, PageSize::Legal, "Legal");
+                        });
+                    ui.checkbox(&mut options.landscape, "Landscape");
+
+                    // scale, fixed scales tile across pages when too big
+                    ui.radio_value(&mut options.scale, PdfScale::Fit, "Fit to page");
+                    if ui.radio(matches!(options.scale, PdfScale::Fixed(_)), "Fixed scale").clicked() && options.scale == PdfScale::Fit {
+                        options.scale = PdfScale::Fixed(1.0);
+                    }
+                    if let PdfScale::Fixed(scale) = &mut options.scale {
+                        ui.add(egui::DragValue::new(scale).speed(0.05).clamp_range(0.1 ..= 10.0).suffix("x"));
+                    }
+
+                    // title block, saved with the diagram
+                    ui.checkbox(&mut options.title_block, "Title block");
+                    if options.title_block {
+                        egui::Grid::new("title_block").show(ui, |ui| {
+                            [
+                                ("Title", &mut metadata.title),
+                                ("Author", &mut metadata.author),
+                                ("Date", &mut metadata.date),
+                                ("Revision", &mut metadata.revision)
+                            ].into_iter().for_each(|(field, value)| {
+                                ui.label(field);
+                                if ui.text_edit_singleline(value).changed() { edited = Some(field); }
+                                ui.end_row();
+                            });
+                        });
+                    }
+
+                    if ui.button("Export").clicked() { export = true; }
+                });
+
+            // typing in a field is one edit until something else happens
+            if let Some(field) = edited {
+                let before = self.objects.clone();
+                self.objects.metadata = metadata;
+                self.history.push_merged(format!("Edit {}", field.to_lowercase()), format!("metadata {field}"), before);
+            }
+
+            if export {
+                // get export location
+                let path = FileDialog::new()
+                    .set_location("~")
+                    .add_filter("PDF", &["pdf"])
+                    .show_save_single_file()
+                    .unwrap();
+
+                if let Some(path) = path {
+                    if let Err(err) = pdf::export_pdf(&self.objects, &path, options) {
+                        self.error = Some(("Could Not Export", err));
+                    }
+                    open = false;
+                }
+            }
+            if !open { self.pdf_export = None; }
+        }
+
+        // if generating sql, draw the mapped schema
+        if let Some(options) = &mut self.sql_export {
+            let mut open = true;
+            let mut save = false;
+
+            // only mapped again when the diagram or the options change, whether or not the change is in the history
+            let current = content_hash(&self.objects);
+            if !self.sql_cache.as_ref().is_some_and(|(made_for, hash, _)| made_for == options && *hash == current) {
+                let mapped = schema::map_objects(&self.objects, options.inheritance).map(|schema| (schema.to_sql(options.dialect), schema.report()));
+                self.sql_cache = Some((options.clone(), current, mapped));
+            }
+            let (_, _, mapped) = self.sql_cache.as_ref().expect("Mapped just above");
+
+            egui::Window::new("Generate SQL")
+                .open(&mut open)
+                .default_width(500.0)
+                .show(ctx, |ui| {
+                    // if mouse contained, make sure to cancel click checks
+                    if ui.rect_contains_pointer(ui.clip_rect()) { skip_click_check = true; }
+
+                    egui::ComboBox::from_label("Dialect")
+                        .selected_text(format!("{:?}", options.dialect))
+                        .show_ui(ui, |ui| {
+                            // yes I know doing this twice is kinda hacky
+                            if ui.rect_contains_pointer(ui.clip_rect()) { skip_click_check = true; }
+
+                            ui.selectable_value(&mut options.dialect, Dialect::Postgres, "PostgreSQL");
+                            ui.selectable_value(&mut options.dialect, Dialect::Sqlite, "SQLite");
+                            ui.selectable_value(&mut options.dialect, Dialect::MySql, "MySQL");
+                        });
+                    egui::ComboBox::from_label("Inheritance")
+                        .selected_text(format!("{:?}", options.inheritance))
+                        .show_ui(ui, |ui| {
+                            // yes I know doing this twice is kinda hacky
+                            if ui.rect_contains_pointer(ui.clip_rect()) { skip_click_check = true; }
+
+                            ui.selectable_value(&mut options.inheritance, Inheritance::ClassTable, "Table per type");
+                            ui.selectable_value(&mut options.inheritance, Inheritance::SingleTable, "Single table");
+                            ui.selectable_value(&mut options.inheritance, Inheritance::ConcreteTable, "Table per concrete class");
+                        });
+
+                    // generated ddl and the rules behind it, or why there is none
+                    match mapped {
+                        Ok((sql, report)) => {
+                            egui::ScrollArea::vertical().id_source("sql").max_height(300.0).show(ui, |ui| {
+                                ui.add(egui::TextEdit::multiline(&mut sql.as_str()).code_editor().desired_width(f32::INFINITY).interactive(false));
+                            });
+                            ui.collapsing("Report", |ui| {
+                                egui::ScrollArea::vertical().id_source("report").max_height(200.0).show(ui, |ui| {
+                                    ui.monospace(report);
+                                });
+                            });
+                        },
+                        Err(err) => { ui.colored_label(Color32::RED, err); }
+                    }
+
+                    if ui.add_enabled(mapped.is_ok(), egui::Button::new("Save SQL")).clicked() { save = true; }
+                });
+
+            if let (true, Ok((sql, _))) = (save, mapped) {
+                // get save location
+                let path = FileDialog::new()
+                    .set_location("~")
+                    .add_filter("SQL", &["sql"])
+                    .show_save_single_file()
+                    .unwrap();
+
+                if let Some(path) = path {
+                    if let Err(err) = std::fs::write(&path, sql) {
+                        self.error = Some(("Could Not Save SQL", err.to_string()));
+                    }
+                }
+            }
+            if !open {
+                self.sql_export = None;
+                self.sql_cache = None;
+            }
+        }
+        // if a single tree is selected, draw the tree edit window
+        let single = if let [id] = self.selected[..] { Some(id) } else { None };
+        if let Some(idx) = self.objects.trees.iter().position(|tree| Some(tree.id) == single) {
+            let tree = &self.objects.trees[idx];
+            let id = tree.id;
+            let kind = tree.root.kind();
+            let violations = tree.root.check();
+            if self.tree_edit.as_ref().map(|(edit, _, _)| *edit) != Some(id) {
+                self.tree_edit = Some((id, tree.root.all_keys().join(", "), tree.root.order()));
+                self.tree_trace.clear();
+            }
+            let mut rebuild = false;
+            let mut operation = None;
+            let mut play = false;
+            let mut remove = false;
+
+            egui::Window::new("Edit Tree")
+                .show(ctx, |ui| {
+                    ui.label(format!("{kind}, ID {:?}", id));
+
+                    // if mouse contained, make sure to cancel click checks
+                    if ui.rect_contains_pointer(ui.clip_rect()) { skip_click_check = true; }
+
+                    // keys can be changed and the tree rebuilt from them
+                    if let Some((_, keys, order)) = &mut self.tree_edit {
+                        ui.add(egui::DragValue::new(order).clamp_range(bminustree::MIN_ORDER ..= 1000).prefix("Order "));
+                        ui.label("Keys");
+                        ui.text_edit_multiline(keys);
+                    }
+                    ui.horizontal(|ui| {
+                        if ui.button("Rebuild").clicked() { rebuild = true; }
+                        if ui.button("Delete").clicked() { remove = true; }
+                        if ui.button("Play Back").clicked() { play = true; }
+                    });
+
+                    // single keys can be inserted, removed or looked up, showing each step taken
+                    ui.separator();
+                    ui.horizontal(|ui| {
+                        ui.add(egui::TextEdit::singleline(&mut self.tree_key).desired_width(80.0).hint_text("Key"));
+                        let key = self.tree_key.trim();
+                        if ui.add_enabled(!key.is_empty(), egui::Button::new("Insert")).clicked() { operation = Some("Insert"); }
+                        if ui.add_enabled(!key.is_empty(), egui::Button::new("Remove")).clicked() { operation = Some("Remove"); }
+                        if ui.add_enabled(!key.is_empty(), egui::Button::new("Search")).clicked() { operation = Some("Search"); }
+                    });
+                    egui::ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
+                        self.tree_trace.iter().for_each(|step| { ui.monospace(step.to_string()); });
+                    });
+
+                    // anything the tree does wrong
+                    ui.separator();
+                    if violations.is_empty() { ui.label(format!("This is a valid {kind}")); }
+                    egui::ScrollArea::vertical().id_source("violations").max_height(200.0).show(ui, |ui| {
+                        violations.iter().for_each(|violation| { ui.colored_label(Color32::RED, violation.to_string()); });
+                    });
+                });
+
+            if let Some(operation) = operation {
+                let before = self.objects.clone();
+                let key = self.tree_key.trim().to_string();
+                let root = &mut self.objects.trees[idx].root;
+                let keys = root.all_keys();
+                self.tree_trace = match operation {
+                    "Insert" => root.insert(&key),
+                    "Remove" => root.delete(&key),
+                    _ => root.search(&key)
+                };
+
+                // only changes to the keys are worth undoing
+                let changed = root.all_keys();
+                if changed != keys {
+                    self.history.push(format!("{operation} {key} in tree"), before);
+                    if let Some((_, keys, _)) = &mut self.tree_edit { *keys = changed.join(", "); }
+                }
+            }
+
+            if rebuild {
+                if let Some((_, keys, order)) = &self.tree_edit {
+                    let options = TreeOptions { order: *order, ..self.objects.trees[idx].root.options() };
+                    let mut keys = split_keys(keys);
+                    bminustree::sort_keys(&mut keys, options.ordering);
+                    self.history.push("Rebuild tree", self.objects.clone());
+                    self.objects.trees[idx].root = TreeRoot::create(keys, &options);
+                    self.tree_edit = None;
+                }
+            }
+            if play {
+                if let Some((_, keys, order)) = &self.tree_edit {
+                    let options = TreeOptions { order: *order, ..self.objects.trees[idx].root.options() };
+                    self.playback = Some(Playback::new(id, keys.clone(), options));
+                }
+            }
+            if remove {
+                self.history.push("Delete tree", self.objects.clone());
+                self.objects.trees.remove(idx);
+                self.selected.clear();
+            }
+        }
+
+        // if creating a tree, ask for its keys
+        if let Some((keys, options)) = &mut self.tree_keys {
+            let mut open = true;
+            let mut create = false;
+            let mut load = false;
+
+            egui::Window::new("Create Tree")
+                .open(&mut open)
+                .show(ctx, |ui| {
+                    // if mouse contained, make sure to cancel click checks
+                    if ui.rect_contains_pointer(ui.clip_rect()) { skip_click_check = true; }
+
+                    ui.horizontal(|ui| {
+                        ui.radio_value(&mut options.kind, TreeKind::BMinus, TreeKind::BMinus.to_string());
+                        ui.radio_value(&mut options.kind, TreeKind::BPlus, TreeKind::BPlus.to_string());
+                    });
+                    ui.add(egui::DragValue::new(&mut options.order).clamp_range(bminustree::MIN_ORDER ..= 1000).prefix("Order "));
+                    ui.horizontal(|ui| {
+                        ui.radio_value(&mut options.ordering, KeyOrder::Numeric, "Numeric");
+                        ui.radio_value(&mut options.ordering, KeyOrder::Lexicographic, "Lexicographic");
+                    });
+
+                    // keys can be split on a common delimiter or any other text
+                    ui.horizontal(|ui| {
+                        let preset = DELIMITERS.iter().find(|(_, delimiter)| *delimiter == options.delimiter).map(|(name, _)| *name);
+                        egui::ComboBox::from_label("Delimiter")
+                            .selected_text(preset.unwrap_or("Other"))
+                            .show_ui(ui, |ui| {
+                                DELIMITERS.iter().for_each(|(name, delimiter)| { ui.selectable_value(&mut options.delimiter, delimiter.to_string(), *name); });
+                                if ui.selectable_label(preset.is_none(), "Other").clicked() && preset.is_some() { options.delimiter = "|".into(); }
+                            });
+                        if preset.is_none() { ui.add(egui::TextEdit::singleline(&mut options.delimiter).desired_width(40.0)); }
+                    });
+
+                    // only the first keys can be used
+                    ui.horizontal(|ui| {
+                        let mut limited = options.limit.is_some();
+                        ui.checkbox(&mut limited, "Only use the first");
+                        if limited != options.limit.is_some() { options.limit = if limited { Some(10) } else { None }; }
+                        if let Some(limit) = &mut options.limit { ui.add(egui::DragValue::new(limit).clamp_range(1 ..= usize::MAX).suffix(" keys")); }
+                    });
+
+                    ui.horizontal(|ui| {
+                        ui.label("Keys");
+                        if ui.button("Load File").clicked() { load = true; }
+                    });
+                    egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| { ui.text_edit_multiline(keys); });
+                    match options.keys(keys) {
+                        Ok(keys) => { ui.label(format!("{} keys", keys.len())); },
+                        Err(err) => { ui.colored_label(Color32::RED, err); }
+                    }
+                    if ui.button("Create").clicked() { create = true; }
+                });
+
+            if load {
+                match open_keys_file() {
+                    Ok(Some(text)) => *keys = text,
+                    Ok(None) => {},
+                    Err(err) => self.error = Some(("Could Not Import", err))
+                }
+            }
+            if create {
+                let before = self.objects.clone();
+                match options.keys(keys).and_then(|keys| self.objects.create_tree(keys, options)) {
+                    Ok(id) => {
+                        self.history.push("Create tree", before);
+                        self.selected = vec![id];
+                        open = false;
+                    },
+                    Err(err) => self.error = Some(("Could Not Create Tree", err))
+                }
+            }
+            if !open { self.tree_keys = None; }
+        }
+
+        // if playing back a tree, show the controls and put the current frame in the tree
+        if let Some(playback) = &mut self.playback {
+            let mut open = true;
+            let mut start = false;
+            let shown = playback.current;
+
+            egui::Window::new("Play Back Tree")
+                .open(&mut open)
+                .show(ctx, |ui| {
+                    // if mouse contained, make sure to cancel click checks
+                    if ui.rect_contains_pointer(ui.clip_rect()) { skip_click_check = true; }
+
+                    ui.add(egui::DragValue::new(&mut playback.options.order).clamp_range(bminustree::MIN_ORDER ..= 1000).prefix("Order "));
+                    ui.label("Keys to insert into an empty tree, in order");
+                    ui.text_edit_multiline(&mut playback.sequence);
+                    if ui.button("Start").clicked() { start = true; }
+
+                    if playback.frames.is_empty() { return }
+                    ui.separator();
+                    ui.horizontal(|ui| {
+                        if ui.button("First").clicked() { playback.current = 0; }
+                        if ui.button("Back").clicked() { playback.back(); }
+                        let play = if playback.playing { "Pause" } else { "Play" };
+                        if ui.button(play).clicked() {
+                            if playback.at_end() { playback.current = 0; }
+                            playback.playing = !playback.playing;
+                        }
+                        if ui.button("Forward").clicked() { playback.forward(); }
+                        if ui.button("Last").clicked() { playback.current = playback.frames.len() - 1; }
+                    });
+                    ui.add(egui::Slider::new(&mut playback.current, 0 ..= playback.frames.len() - 1).text("Step"));
+                    ui.add(egui::DragValue::new(&mut playback.interval).clamp_range(0.05 ..= 5.0).speed(0.05).suffix(" s per step"));
+                    if let Some(frame) = playback.frame() { ui.monospace(&frame.text); }
+                });
+
+            playback.tick(ctx.input(|i| i.stable_dt));
+            if playback.playing { ctx.request_repaint(); }
+
+            // the whole playback is one step in the history
+            if start {
+                self.history.push("Play back tree", self.objects.clone());
+                playback.record(&split_keys(&playback.sequence));
+            }
+            if start || playback.current != shown {
+                if let (Some(frame), Some(tree)) = (playback.frame(), self.objects.trees.iter_mut().find(|tree| tree.id == playback.tree)) {
+                    tree.root = frame.root.clone();
+                    self.tree_edit = None;
+                }
+            }
+            if !self.objects.trees.iter().any(|tree| tree.id == playback.tree) { open = false; }
+            if !open { self.playback = None; }
+        }
+
+        // if objects are selected, draw the element edit window for all of them at once
+        let group_ids = self.selected.iter().copied().filter(|id| self.objects.objects.iter().any(|a| a.id == *id)).collect::<Vec<u32>>();
+        if !group_ids.is_empty() {
+            let single = if let [id] = group_ids[..] { Some(id) } else { None };
+
+            // only what the window can change is kept, the whole diagram is copied if it does change
+            let old_objects = self.objects.objects.iter().filter(|a| group_ids.contains(&a.id)).cloned().collect::<Vec<Object>>();
+            let old_links = self.objects.links.iter().enumerate()
+                .filter(|(_, a)| Some(a.a) == single || Some(a.b) == single)
+                .map(|(idx, link)| (idx, link.clone()))
+                .collect::<Vec<(usize, Link)>>();
+            let what = describe(&self.objects, &group_ids);
+            let found = self.objects.objects.iter().find(|a| a.name.eq_ignore_ascii_case(self.search.as_str()) && Some(a.id) != single && !self.search.is_empty()).cloned();
             let mut connected_to = self.objects.links.iter_mut()
-                .filter(|a| Some(a.a) == self.selected || Some(a.b) == self.selected)
+                .filter(|a| Some(a.a) == single || Some(a.b) == single)
                 .collect::<Vec<&mut Link>>();
             let mut connected_to = connected_to.iter_mut()
-                .map(|link| {
-                    (self.objects.objects.clone().iter().find(|a| (a.id == link.a || a.id == link.b) && Some(a.id) != self.selected).unwrap().clone(), link)
+                .filter_map(|link| {
+                    Some((self.objects.objects.iter().find(|a| (a.id == link.a || a.id == link.b) && Some(a.id) != single)?.clone(), link))
                 })
                 .collect::<Vec<(Object, &mut &mut Link)>>();
-            let selected = self.objects.objects.iter_mut().find(|a| Some(a.id) == self.selected).unwrap();
-            let mut to_remove: Option<u32> = None;
+            let mut group = self.objects.objects.iter_mut().filter(|a| group_ids.contains(&a.id)).collect::<Vec<&mut Object>>();
             let mut remove_link: Option<(u32, u32)> = None;
             let mut link = false;
+            let mut edited: Option<(String, Option<String>)> = None;
 
             // draw window
             egui::Window::new("Edit Element")
                 .show(ctx, |ui| {
-                    ui.label(format!("ID {:?}", selected.id));
+                    match single {
+                        Some(id) => ui.label(format!("ID {:?}", id)),
+                        None => ui.label(format!("{} objects", group.len()))
+                    };
 
                     // if mouse contained, make sure to cancel click checks
                     if ui.rect_contains_pointer(ui.clip_rect()) { skip_click_check = true; }
 
-                    // select type
+                    // select type, objects that already have it are left alone
                     let mut combo_changed = false;
+                    let same = group.iter().all(|a| a.object_type == group[0].object_type);
                     egui::ComboBox::from_label("Object Type")
-                        .selected_text(format!("{:?}", selected.object_type))
+                        .selected_text(if same { format!("{:?}", group[0].object_type) } else { "Mixed".into() })
                         .show_ui(ui, |ui| {
                             // yes I know doing this twice is kinda hacky
                             if ui.rect_contains_pointer(ui.clip_rect()) { skip_click_check = true; }
 
-                            // options
-                            let a = ui.selectable_value(
-                                &mut selected.object_type, 
-                                ObjectType::Entity, 
-                                "Entity"
-                            );
-                            let b = ui.selectable_value(
-                                &mut selected.object_type, 
-                                ObjectType::EntityDependent, 
-                                "Entity Dependent"
-                            );
-                            let c = ui.selectable_value(
-                                &mut selected.object_type, 
-                                ObjectType::Relationship { card: objects::CardType::OneToOne }, 
-                                "Relationship"
-                            );
-                            let d = ui.selectable_value(
-                                &mut selected.object_type, 
-                                ObjectType::RelationshipDependent { card: objects::CardType::OneToOne }, 
-                                "Relationship Dependent"
-                            );
-                            let e = ui.selectable_value(
-                                &mut selected.object_type, 
-                                ObjectType::Parameter { is_id: false }, 
-                                "Parameter"
-                            );
-                            let f = ui.selectable_value(
-                                &mut selected.object_type, 
-                                ObjectType::FunctionParameter { is_id: false }, 
-                                "Functional Parameter"
-                            );
-                            let g = ui.selectable_value(
-                                &mut selected.object_type, 
-                                ObjectType::Polymorph { poly: objects::Polymorph::Union }, 
-                                "Polymorph"
-                            );
-
-                            // update combo changed
-                            if a.clicked() || b.clicked() || c.clicked() || d.clicked() || e.clicked() || f.clicked() || g.clicked() { combo_changed = true; }
+                            object_types().into_iter().for_each(|(object_type, label)| {
+                                let kind = std::mem::discriminant(&object_type);
+                                let chosen = group.iter().all(|a| std::mem::discriminant(&a.object_type) == kind);
+                                if ui.selectable_label(chosen, label).clicked() {
+                                    group.iter_mut()
+                                        .filter(|a| std::mem::discriminant(&a.object_type) != kind)
+                                        .for_each(|a| a.object_type = object_type.clone());
+                                    combo_changed = true;
+                                    edited = Some((format!("Change type of {what}"), None));
+                                }
+                            });
                         });
 
-                    // edit name
-                    let edit = ui.text_edit_singleline(&mut selected.name);
-
-                    match &mut selected.object_type {
-                        ObjectType::Relationship { card } |
-                        ObjectType::RelationshipDependent { card } => {
-                            egui::ComboBox::from_label("Card Type")
-                                .selected_text(format!("{:?}", card))
-                                .show_ui(ui, |ui| {
-                                    // yes I know doing this twice is kinda hacky
-                                    if ui.rect_contains_pointer(ui.clip_rect()) { skip_click_check = true; }
-
-                                    let a = ui.selectable_value(card, CardType::OneToOne, "One To One");
-                                    let b = ui.selectable_value(card, CardType::OneToMany, "One To Many");
-                                    let c = ui.selectable_value(card, CardType::ManyToOne, "Many To One");
-                                    let d = ui.selectable_value(card, CardType::ManyToMany, "Many To Many");
-
-                                    // update combo changed
-                                    if a.clicked() || b.clicked() || c.clicked() || d.clicked() { combo_changed = true; }
+                    // edit name, only one object at a time
+                    let renamed = single.is_some() && ui.text_edit_singleline(&mut group[0].name).changed();
+
+                    // shared properties show when every object has them
+                    let cards = group.iter().filter_map(|a| match &a.object_type {
+                        ObjectType::Relationship { card } | ObjectType::RelationshipDependent { card } => Some(card.clone()),
+                        _ => None
+                    }).collect::<Vec<CardType>>();
+                    if cards.len() == group.len() {
+                        let mut chosen = None;
+                        egui::ComboBox::from_label("Card Type")
+                            .selected_text(if cards.iter().all(|a| *a == cards[0]) { format!("{:?}", cards[0]) } else { "Mixed".into() })
+                            .show_ui(ui, |ui| {
+                                // yes I know doing this twice is kinda hacky
+                                if ui.rect_contains_pointer(ui.clip_rect()) { skip_click_check = true; }
+
+                                [
+                                    (CardType::OneToOne, "One To One"),
+                                    (CardType::OneToMany, "One To Many"),
+                                    (CardType::ManyToOne, "Many To One"),
+                                    (CardType::ManyToMany, "Many To Many")
+                                ].into_iter().for_each(|(card, label)| {
+                                    if ui.selectable_label(cards.iter().all(|a| *a == card), label).clicked() { chosen = Some(card); }
                                 });
-                        },
-                        ObjectType::Parameter { is_id } |
-                        ObjectType::FunctionParameter { is_id } => {
-                            ui.checkbox(is_id, "Is ID?");
+                            });
+
+                        if let Some(chosen) = chosen {
+                            group.iter_mut().for_each(|a| {
+                                if let ObjectType::Relationship { card } | ObjectType::RelationshipDependent { card } = &mut a.object_type { *card = chosen.clone(); }
+                            });
+                            combo_changed = true;
+                            edited = Some((format!("Change cardinality of {what}"), None));
                         }
-                        ObjectType::Polymorph { poly } => {
-                            egui::ComboBox::from_label("Polymorph Type")
-                                .selected_text(format!("{:?}", poly))
-                                .show_ui(ui, |ui| {
-                                    // yes I know doing this twice is kinda hacky
-                                    if ui.rect_contains_pointer(ui.clip_rect()) { skip_click_check = true; }
-
-                                    let a = ui.selectable_value(poly, objects::Polymorph::Union, "Union");
-                                    let b = ui.selectable_value(poly, objects::Polymorph::Disjoint, "Disjoin");
-                                    let c = ui.selectable_value(poly, objects::Polymorph::Overlapping, "Overlapping");
-
-                                    // update combo changed
-                                    if a.clicked() || b.clicked() || c.clicked() { combo_changed = true; }
+                    }
+
+                    let ids = group.iter().filter_map(|a| match a.object_type {
+                        ObjectType::Parameter { is_id } | ObjectType::FunctionParameter { is_id } => Some(is_id),
+                        _ => None
+                    }).collect::<Vec<bool>>();
+                    if ids.len() == group.len() {
+                        let mut all = ids.iter().all(|a| *a);
+                        if ui.checkbox(&mut all, "Is ID?").changed() {
+                            group.iter_mut().for_each(|a| {
+                                if let ObjectType::Parameter { is_id } | ObjectType::FunctionParameter { is_id } = &mut a.object_type { *is_id = all; }
+                            });
+                            edited = Some((format!("Change ID of {what}"), None));
+                        }
+                    }
+
+                    let polys = group.iter().filter_map(|a| match &a.object_type {
+                        ObjectType::Polymorph { poly } => Some(poly.clone()),
+                        _ => None
+                    }).collect::<Vec<objects::Polymorph>>();
+                    if polys.len() == group.len() {
+                        let mut chosen = None;
+                        egui::ComboBox::from_label("Polymorph Type")
+                            .selected_text(if polys.iter().all(|a| *a == polys[0]) { format!("{:?}", polys[0]) } else { "Mixed".into() })
+                            .show_ui(ui, |ui| {
+                                // yes I know doing this twice is kinda hacky
+                                if ui.rect_contains_pointer(ui.clip_rect()) { skip_click_check = true; }
+
+                                [
+                                    (objects::Polymorph::Union, "Union"),
+                                    (objects::Polymorph::Disjoint, "Disjoin"),
+                                    (objects::Polymorph::Overlapping, "Overlapping")
+                                ].into_iter().for_each(|(poly, label)| {
+                                    if ui.selectable_label(polys.iter().all(|a| *a == poly), label).clicked() { chosen = Some(poly); }
                                 });
+                            });
+
+                        if let Some(chosen) = chosen {
+                            group.iter_mut().for_each(|a| {
+                                if let ObjectType::Polymorph { poly } = &mut a.object_type { *poly = chosen.clone(); }
+                            });
+                            combo_changed = true;
+                            edited = Some((format!("Change polymorph of {what}"), None));
                         }
-                        _ => {}
                     }
 
                     // do text formatting
-                    if edit.changed() || combo_changed {
-                        // selected.name = selected.name;
-                        match selected.object_type {
-                            ObjectType::Entity | 
-                            ObjectType::EntityDependent | 
-                            ObjectType::Relationship { .. } | 
-                            ObjectType::RelationshipDependent { .. } => {
-                                selected.name = selected.name.to_uppercase().replace(" ", "_");
-                            },
-                            ObjectType::Parameter { .. } |
-                            ObjectType::FunctionParameter { .. }  => {}
-                            ObjectType::Polymorph { .. } => { selected.name = format!("{}_poly", selected.name.to_lowercase().replace("_poly", "")) }
-                        }
+                    if renamed || combo_changed { group.iter_mut().for_each(|a| format_name(a)); }
+
+                    // typing a name is one edit until something else happens
+                    if renamed { edited = Some((format!("Rename {}", group[0].name), Some(format!("rename {}", group[0].id)))); }
+
+                    // pinned objects stay put when arranging
+                    let mut pinned = group.iter().all(|a| a.pinned);
+                    if ui.checkbox(&mut pinned, "Pinned").changed() {
+                        group.iter_mut().for_each(|a| a.pinned = pinned);
+                        edited = Some((format!("{} {what}", if pinned { "Pin" } else { "Unpin" }), None));
                     }
 
+                    // links are edited one object at a time
+                    let Some(id) = single else { return };
+
                     // add links
                     ui.collapsing("Links", |ui| {
                         connected_to.iter_mut().for_each(|(other, other_link)| {
@@ -302,9 +1441,14 @@ impl eframe::App for App {
                                 // let other = self.objects.objects.iter().find(|a| a.id == other.a || a.id == other.b).unwrap();
                                 ui.label(format!("-> {}", other.name));
                                 if ui.button("Remove").clicked() {
-                                    remove_link = Some((selected.id, other.id));
+                                    remove_link = Some((id, other.id));
+                                }
+                                if ui.text_edit_singleline(&mut other_link.minmax).changed() {
+                                    edited = Some((
+                                        format!("Edit minmax of {} - {}", group[0].name, other.name),
+                                        Some(format!("minmax {} {}", id, other.id))
+                                    ));
                                 }
-                                ui.text_edit_singleline(&mut other_link.minmax);
                             });
                         });
                     });
@@ -319,35 +1463,38 @@ impl eframe::App for App {
                             link = true;
                         }
                     });
-
-                    // delete button
-                    if delete {
-                        to_remove = Some(selected.id);
-                        self.selected = None;
-                    }
                 });
 
-            // remove marked element if necessary
-            if to_remove.is_some() {
-                // remove original object
-                let idx = self.objects.objects.iter().position(|o| o.id == to_remove.unwrap()).unwrap();
-                let object = self.objects.objects.remove(idx);
-
-                // remove links
-                self.objects.links.iter().enumerate()
-                    .filter(|(_, a)| a.a == object.id || a.b == object.id)
-                    .for_each(|(a, _)| { self.objects.objects.remove(a); });
+            // the diagram from before this edit
+            let before = (edited.is_some() || remove_link.is_some() || link).then(|| {
+                let mut before = self.objects.clone();
+                before.objects.iter_mut().for_each(|object| {
+                    if let Some(old) = old_objects.iter().find(|old| old.id == object.id) { *object = old.clone(); }
+                });
+                old_links.iter().for_each(|(idx, link)| before.links[*idx] = link.clone());
+                before
+            });
+            let name = |id: u32| before.iter().flat_map(|before| before.objects.iter()).find(|a| a.id == id).map(|a| a.name.clone()).unwrap_or_default();
+            if let Some((a, b)) = remove_link {
+                let idx = self.objects.links.iter().position(|link| (link.a == a || link.a == b) && (link.b == a || link.b == b));
+                if let Some(idx) = idx {
+                    self.objects.links.remove(idx);
+                    edited = Some((format!("Unlink {} from {}", name(a), name(b)), None));
+                }
             }
 
-            if remove_link.is_some() {
-                let (a, b) = remove_link.unwrap();
-                let idx = self.objects.links.iter().position(|link| (link.a == a || link.a == b) && (link.b == a || link.b == b));
-                if idx.is_some() { self.objects.links.remove(idx.unwrap()); }
+            if let (true, Some(id), Some(found)) = (link, single, found) {
+                self.objects.links.push(Link { a: id, b: found.id, minmax: String::new() });
+                edited = Some((format!("Link {} to {}", name(id), found.name), None));
             }
 
-            if link {
-                self.objects.links.push(Link { a: self.selected.unwrap(), b: found.unwrap().id, minmax: String::new() });
+            // remember the diagram from before this edit
+            match (edited, before) {
+                (Some((label, Some(key))), Some(before)) => self.history.push_merged(label, key, before),
+                (Some((label, None)), Some(before)) => self.history.push(label, before),
+                _ => {}
             }
+            if single.is_none() && !self.search.is_empty() { self.search = String::new() }
         } else {
             if !self.search.is_empty() { self.search = String::new() }
         }
@@ -362,21 +1509,114 @@ impl eframe::App for App {
                 self.clip = clip;
                 let mut shapes = vec![];
 
+                // the wheel zooms around the pointer, dragging with the right or middle button pans
+                if !skip_click_check && clip.contains(mouse_position) {
+                    let (zoom, pan) = ui.input(|input| {
+                        let zoom = input.zoom_delta() * (input.smooth_scroll_delta.y / 200.0).exp();
+                        let panning = input.pointer.button_down(egui::PointerButton::Secondary) || input.pointer.button_down(egui::PointerButton::Middle);
+                        (zoom, if panning { input.pointer.delta() } else { Vec2::ZERO })
+                    });
+                    if zoom != 1.0 { self.zoom_around(mouse_position, self.zoom * zoom); }
+                    self.scroll_offset += pan;
+                }
+
+                // objects are drawn at normal size around the origin and scaled afterwards, so the pointer is scaled the other way
+                let origin = clip.center() + self.scroll_offset.to_vec2();
+                let pointer = origin + (mouse_position - origin) / self.zoom;
+
                 // setup state
-                let mut state = AppState { clip, mouse_position, scroll_offset: self.scroll_offset, selected: self.selected, click, delete, dragging, skip_click_check };
+                let tree_marks = self.playback.as_ref().and_then(|playback| playback.frame().map(|frame| (playback.tree, frame.marks.clone())));
+                let mut state = AppState { clip, mouse_position: pointer, scroll_offset: self.scroll_offset, selected: self.selected.clone(), hovered: None, highlighted, tree_marks };
 
                 // draw objects
                 let mut card_ids = Vec::new();
                 let mut union_ids = Vec::new();
                 self.objects.objects.iter_mut().for_each(|obj| shapes.extend(draw_object(obj, ui, &mut state)));
                 self.objects.links.iter().for_each(|link| {
-                    let a = self.objects.objects.iter().find(|a| a.id == link.a).unwrap();
-                    let b = self.objects.objects.iter().find(|a| a.id == link.b).unwrap();
+                    // links to missing objects are reported by the validator instead
+                    let a = self.objects.objects.iter().find(|a| a.id == link.a);
+                    let b = self.objects.objects.iter().find(|a| a.id == link.b);
+                    let (Some(a), Some(b)) = (a, b) else { return };
                     shapes.extend(draw_link(&mut card_ids, &mut union_ids, a, b, ui, &mut state, &link.minmax));
                 });
+                self.objects.trees.iter().for_each(|tree| shapes.extend(draw_tree(tree, ui, &mut state)));
+                if self.zoom != 1.0 { ui.fonts(|fonts| zoom::zoom_shapes(fonts, &mut shapes, origin, self.zoom)); }
+                if self.show_grid { ui.painter().extend(align::grid(clip, origin, self.grid_spacing * self.zoom)); }
+
+                // pressing an object selects it, shift adds or removes it, pressing empty space starts a selection rectangle
+                if pressed && !skip_click_check && clip.contains(mouse_position) {
+                    match state.hovered {
+                        Some(id) => {
+                            let was_selected = self.selected.contains(&id);
+                            if shift && was_selected { self.selected.retain(|a| *a != id); }
+                            else if shift { self.selected.push(id); }
+                            else if !was_selected { self.selected = vec![id]; }
+
+                            // the whole selection moves together, pressing one of several picks it alone unless it is dragged
+                            if self.selected.contains(&id) {
+                                self.moving = Some((mouse_position, self.objects.clone(), id, !shift && was_selected));
+                            }
+                        },
+                        None => self.selecting = Some(mouse_position)
+                    }
+                }
 
-                // sync
-                self.selected = state.selected;
+                // move everything selected by how far the pointer went, a whole move is one edit
+                if let Some((start, before, grabbed, _)) = &self.moving {
+                    let mut delta = (mouse_position - *start) / self.zoom;
+
+                    // the grabbed item lines up with anything nearby that stays put, otherwise with the grid
+                    if let (true, Some(area), Some((x, y))) = (delta != Vec2::ZERO, before.item_bounds(*grabbed), before.position(*grabbed)) {
+                        let others = before.objects.iter().map(|a| a.id).chain(before.trees.iter().map(|a| a.id))
+                            .filter(|id| !self.selected.contains(id))
+                            .filter_map(|id| before.item_bounds(id))
+                            .collect::<Vec<Rect>>();
+                        let guides = align::line_up(area.translate(delta), &others, align::GUIDE_DISTANCE / self.zoom);
+                        let stroke = Stroke { width: 1.0, color: Color32::from_rgb(255, 0, 200) };
+                        [x, y].into_iter().enumerate().for_each(|(axis, at)| match guides[axis] {
+                            Some((shift, line)) => {
+                                delta[axis] += shift;
+                                shapes.push(match axis {
+                                    0 => Shape::LineSegment { points: [pos2(origin.x + line * self.zoom, clip.min.y), pos2(origin.x + line * self.zoom, clip.max.y)], stroke },
+                                    _ => Shape::LineSegment { points: [pos2(clip.min.x, origin.y + line * self.zoom), pos2(clip.max.x, origin.y + line * self.zoom)], stroke }
+                                });
+                            },
+                            None if self.snap_to_grid => delta[axis] = align::snap(at + delta[axis], self.grid_spacing) - at,
+                            None => {}
+                        });
+                    }
+                    self.selected.iter().for_each(|id| {
+                        if let Some((x, y)) = before.position(*id) { self.objects.set_position(*id, x + delta.x, y + delta.y); }
+                    });
+                    self.objects.objects.iter_mut().for_each(|a| a.dragging = dragging && self.selected.contains(&a.id));
+                    self.objects.trees.iter_mut().for_each(|a| a.dragging = dragging && self.selected.contains(&a.id));
+                }
+                if !dragging {
+                    if let Some((_, before, grabbed, alone)) = self.moving.take() {
+                        let moved = self.selected.iter().copied().filter(|id| before.position(*id) != self.objects.position(*id)).collect::<Vec<u32>>();
+                        if !moved.is_empty() { self.history.push(format!("Move {}", describe(&self.objects, &moved)), before); }
+                        else if alone { self.selected = vec![grabbed]; }
+                    }
+                }
+
+                // select everything the rectangle touches, shift adds to the selection
+                if let Some(start) = self.selecting {
+                    let band = Rect::from_two_pos(start, mouse_position);
+                    shapes.push(Shape::rect_filled(band, 0.0, Color32::from_rgba_unmultiplied(0, 0, 255, 20)));
+                    shapes.push(Shape::rect_stroke(band, 0.0, Stroke { width: 1.0, color: Color32::BLUE }));
+
+                    if !dragging {
+                        let band = Rect::from_two_pos(self.canvas_position(start), self.canvas_position(mouse_position));
+                        let inside = self.objects.objects.iter().map(|a| (a.id, a.bounds()))
+                            .chain(self.objects.trees.iter().map(|a| (a.id, a.bounds())))
+                            .filter(|(_, bounds)| bounds.intersects(band))
+                            .map(|(id, _)| id)
+                            .collect::<Vec<u32>>();
+                        if !shift { self.selected.clear(); }
+                        inside.into_iter().for_each(|id| if !self.selected.contains(&id) { self.selected.push(id) });
+                        self.selecting = None;
+                    }
+                }
 
                 // finalize draw
                 ui.painter().extend(shapes);
@@ -385,16 +1625,87 @@ impl eframe::App for App {
     }
 }
 
+// names one object or tree, or counts several
+fn describe(objects: &Objects, ids: &[u32]) -> String {
+    match ids {
+        [id] => objects.objects.iter().find(|a| a.id == *id).map(|a| a.name.clone()).unwrap_or_else(|| "tree".into()),
+        _ => format!("{} items", ids.len())
+    }
+}
+
+// every object type with the name shown for it, new objects of a type start with these properties
+fn object_types() -> [(ObjectType, &'static str); 7] {
+    [
+        (ObjectType::Entity, "Entity"),
+        (ObjectType::EntityDependent, "Entity Dependent"),
+        (ObjectType::Relationship { card: CardType::OneToOne }, "Relationship"),
+        (ObjectType::RelationshipDependent { card: CardType::OneToOne }, "Relationship Dependent"),
+        (ObjectType::Parameter { is_id: false }, "Parameter"),
+        (ObjectType::FunctionParameter { is_id: false }, "Functional Parameter"),
+        (ObjectType::Polymorph { poly: objects::Polymorph::Union }, "Polymorph")
+    ]
+}
+
+// names follow the convention of their type
+fn format_name(object: &mut Object) {
+    match object.object_type {
+        ObjectType::Entity | 
+        ObjectType::EntityDependent | 
+        ObjectType::Relationship { .. } | 
+        ObjectType::RelationshipDependent { .. } => {
+            object.name = object.name.to_uppercase().replace(" ", "_");
+        },
+        ObjectType::Parameter { .. } |
+        ObjectType::FunctionParameter { .. }  => {}
+        ObjectType::Polymorph { .. } => { object.name = format!("{}_poly", object.name.to_lowercase().replace("_poly", "")) }
+    }
+}
+
+// hashes everything saved in a diagram, so any change to it gives a different hash
+fn content_hash(objects: &Objects) -> u64 {
+    let mut hasher = std::hash::DefaultHasher::new();
+    serde_json::to_string(objects).unwrap_or_default().hash(&mut hasher);
+    hasher.finish()
+}
+
+// delimiters keys are usually separated by, empty is any whitespace
+const DELIMITERS: [(&str, &str); 5] = [("New line", "\n"), ("Comma", ","), ("Semicolon", ";"), ("Tab", "\t"), ("Whitespace", "")];
+
+// asks for a file of keys and reads it, nothing if no file was picked
+fn open_keys_file() -> Result<Option<String>, String> {
+    let path = FileDialog::new()
+        .set_location("~")
+        .add_filter("Keys", &["txt", "csv"])
+        .show_open_single_file()
+        .unwrap();
+    path.map(|path| std::fs::read_to_string(path).map_err(|err| err.to_string())).transpose()
+}
+
+// splits a typed key list on commas and new lines
+fn split_keys(text: &str) -> Vec<String> {
+    text.split([',', '\n']).map(|key| key.trim().to_string()).filter(|key| !key.is_empty()).collect()
+}
+
 fn main() {
     let args = std::env::args().collect::<Vec<String>>();
-    if args.len() >= 3 && args[1] == "screenshot" {
-        println!("Screenshot");
-        let path = PathBuf::from_str(args[2].as_str());
-
-        // do screenshot
-        let objects: Objects = serde_json::from_str(std::fs::read_to_string(path.unwrap()).unwrap().as_str()).unwrap();
-        screenshot::screenshot(objects);
+    if let Some(result) = cli::run(&args[1 ..]) {
+        // commands never open the editor, and all but screenshot work without a display
+        if let Err(failure) = result {
+            eprintln!("{failure}");
+            std::process::exit(failure.exit_code());
+        }
     } else {
+        // a single argument is a diagram to open, --new starts with an empty canvas, anything else is likely a mistyped command
+        let open = match &args[1 ..] {
+            [] => None,
+            [new] if new == "--new" => None,
+            [path] if !path.starts_with("--") && (Path::new(path).exists() || Path::new(path).extension().is_some()) => Some(PathBuf::from(path)),
+            _ => {
+                eprintln!("{}", cli::USAGE);
+                std::process::exit(2);
+            }
+        };
+
         // create default window options
         let native_options = eframe::NativeOptions {
             viewport: egui::ViewportBuilder::default()
@@ -407,7 +1718,7 @@ fn main() {
         eframe::run_native(
             "Entity Relationship Editor", 
             native_options, 
-            Box::new(|ctx| Box::new(App::from_context(ctx)))
+            Box::new(|ctx| Box::new(App::from_context(ctx, open)))
         ).unwrap();
     }
 }
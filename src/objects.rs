@@ -1,20 +1,35 @@
+use egui::{pos2, Rect, Vec2};
 use serde::{Deserialize, Serialize};
 
-use crate::bminustree::BMinusNode;
+use crate::bminustree::{BTree, TreeOptions, TreeRoot, MIN_ORDER};
 
 #[derive(Clone, Default, Debug, Serialize, Deserialize)]
-#[serde(default)]
+#[serde(default, deny_unknown_fields)]
 pub struct Objects {
     pub objects: Vec<Object>,
     pub links: Vec<Link>,
-    pub trees: Vec<BMinusNode>,
-    pub next_id: u32
+    pub trees: Vec<BTree>,
+    pub next_id: u32,
+    pub metadata: Metadata,
+    // the file format version, only read so it is not an unknown field, saving writes the current one
+    #[serde(skip_serializing)]
+    pub version: Option<u64>
+}
+
+// describes the diagram, shown in the title block of exports
+#[derive(Clone, Default, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Metadata {
+    pub title: String,
+    pub author: String,
+    pub date: String,
+    pub revision: String
 }
 
 impl Objects {
     // adds a object to this state
     pub fn add(&mut self, object_type: ObjectType, x: f32, y: f32) -> &mut Object {
-        self.objects.push(Object { id: self.next_id, x, y, width: 0.0, height: 0.0, name: String::new(), object_type, dragging: false });
+        self.objects.push(Object { id: self.next_id, x, y, width: 0.0, height: 0.0, name: String::new(), object_type, dragging: false, pinned: false });
         self.next_id += 1;
         self.objects.iter_mut().last().expect("Physics just broke")
     }
@@ -22,8 +37,8 @@ impl Objects {
     // removes a link between A and B
     pub fn remove_link(&mut self, a: u32, b: u32) -> bool {
         let idx = self.links.iter().position(|link| (link.a == a || link.a == b) && (link.b == a || link.b == b));
-        if idx.is_some() {
-            self.links.remove(idx.unwrap());
+        if let Some(idx) = idx {
+            self.links.remove(idx);
             true
         } else { false }
     }
@@ -35,25 +50,123 @@ impl Objects {
     pub fn get_link(&self, node: u32) -> Option<&Link> { self.links.iter().find(|a| a.a == node || a.b == node) }
     pub fn get_link_mut(&mut self, node: u32) -> Option<&mut Link> { self.links.iter_mut().find(|a| a.a == node || a.b == node) }
 
-    pub fn create_tree(&mut self, keys: Vec<String>) {
-        if keys.is_empty() { return }
-        let created = BMinusNode::create(keys);
-        created.debug();
-        self.trees.push(created);
+    // checks if an object or tree has the given id
+    pub fn contains(&self, id: u32) -> bool {
+        self.objects.iter().any(|object| object.id == id) || self.trees.iter().any(|tree| tree.id == id)
+    }
+
+    // gets the position of an object or tree
+    pub fn position(&self, id: u32) -> Option<(f32, f32)> {
+        self.objects.iter().find(|object| object.id == id).map(|object| (object.x, object.y))
+            .or_else(|| self.trees.iter().find(|tree| tree.id == id).map(|tree| (tree.x, tree.y)))
+    }
+
+    // moves an object or tree
+    pub fn set_position(&mut self, id: u32, x: f32, y: f32) {
+        self.objects.iter_mut().filter(|object| object.id == id).for_each(|object| { object.x = x; object.y = y; });
+        self.trees.iter_mut().filter(|tree| tree.id == id).for_each(|tree| { tree.x = x; tree.y = y; });
+    }
+
+    // gets the area covered by an object or tree
+    pub fn item_bounds(&self, id: u32) -> Option<Rect> {
+        self.objects.iter().find(|object| object.id == id).map(|object| object.bounds())
+            .or_else(|| self.trees.iter().find(|tree| tree.id == id).map(|tree| tree.bounds()))
+    }
+
+    // gets the area covered by all objects and trees, relative to the canvas center
+    pub fn bounds(&self) -> Option<Rect> {
+        self.objects.iter()
+            .map(|object| object.bounds())
+            .chain(self.trees.iter().map(|tree| tree.bounds()))
+            .reduce(|a, b| a.union(b))
+    }
+
+    // copies objects and trees with the links among them, as a diagram of their own
+    pub fn extract(&self, ids: &[u32]) -> Objects {
+        Objects {
+            objects: self.objects.iter().filter(|object| ids.contains(&object.id)).cloned().collect(),
+            links: self.links.iter().filter(|link| ids.contains(&link.a) && ids.contains(&link.b)).cloned().collect(),
+            trees: self.trees.iter().filter(|tree| ids.contains(&tree.id)).cloned().collect(),
+            ..Objects::default()
+        }
+    }
+
+    // adds copies of everything in another diagram with fresh ids, moved by an offset, returning the new ids
+    pub fn paste(&mut self, other: &Objects, offset: Vec2) -> Vec<u32> {
+        let mut ids = Vec::new();
+        other.objects.iter().for_each(|object| {
+            ids.push((object.id, self.next_id));
+            self.objects.push(Object { id: self.next_id, x: object.x + offset.x, y: object.y + offset.y, dragging: false, ..object.clone() });
+            self.next_id += 1;
+        });
+        other.trees.iter().for_each(|tree| {
+            ids.push((tree.id, self.next_id));
+            self.trees.push(BTree { id: self.next_id, x: tree.x + offset.x, y: tree.y + offset.y, dragging: false, root: tree.root.clone() });
+            self.next_id += 1;
+        });
+
+        // links follow their objects, links to anything that was not copied are dropped
+        let new_id = |id: u32| ids.iter().find(|(old, _)| *old == id).map(|(_, new)| *new);
+        let links = other.links.iter()
+            .filter_map(|link| Some(Link { a: new_id(link.a)?, b: new_id(link.b)?, minmax: link.minmax.clone() }))
+            .collect::<Vec<Link>>();
+        self.links.extend(links);
+        ids.into_iter().map(|(_, new)| new).collect()
+    }
+
+    // builds a tree of the given order from sorted keys and places it below everything else
+    pub fn create_tree(&mut self, keys: Vec<String>, options: &TreeOptions) -> Result<u32, String> {
+        if options.order < MIN_ORDER { return Err(format!("A tree needs an order of at least {MIN_ORDER}")) }
+        let created = TreeRoot::create(keys, options);
+        Ok(self.add_tree(created))
+    }
+
+    // adds a tree below everything else
+    pub fn add_tree(&mut self, root: TreeRoot) -> u32 {
+        let y = self.bounds().map(|bounds| bounds.max.y + 80.0).unwrap_or(0.0);
+        let id = self.next_id;
+        self.trees.push(BTree::new(id, 0.0, y, root));
+        self.next_id += 1;
+        id
     }
 }
 
+// an object without an id, name or type cannot be placed in the diagram, so those have to be in the file
 #[derive(Clone, Default, Debug, Serialize, Deserialize)]
-#[serde(default)]
+#[serde(deny_unknown_fields)]
 pub struct Object {
     pub id: u32,
+    #[serde(default)]
     pub x: f32,
+    #[serde(default)]
     pub y: f32,
+    #[serde(default)]
     pub width: f32,
+    #[serde(default)]
     pub height: f32,
     pub name: String,
     pub object_type: ObjectType,
-    pub dragging: bool
+    #[serde(default)]
+    pub dragging: bool,
+    // kept in place when arranging
+    #[serde(default)]
+    pub pinned: bool
+}
+
+impl Object {
+    // gets the area this object covers when drawn, using the size from the last draw
+    pub fn bounds(&self) -> Rect {
+        let (half_width, half_height) = match self.object_type {
+            ObjectType::Entity => (self.width / 2.0, self.height / 2.0),
+            ObjectType::EntityDependent => (self.width / 2.0 + 5.0, self.height / 2.0 + 5.0),
+            ObjectType::Relationship { .. } => (self.width / 2.0, self.width / 2.0),
+            ObjectType::RelationshipDependent { .. } => (self.width / 2.0 + 5.0, self.width / 2.0 + 5.0),
+            ObjectType::Parameter { .. } |
+            ObjectType::FunctionParameter { .. } |
+            ObjectType::Polymorph { .. } => (self.width / 2.0, self.height / 2.0)
+        };
+        Rect { min: pos2(self.x - half_width, self.y - half_height), max: pos2(self.x + half_width, self.y + half_height) }
+    }
 }
 
 #[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, PartialOrd)]
@@ -112,7 +225,7 @@ impl ObjectType {
 }
 
 #[derive(Clone, Default, Debug, Serialize, Deserialize)]
-#[serde(default)]
+#[serde(default, deny_unknown_fields)]
 pub struct Link {
     pub a: u32,
     pub b: u32,
@@ -0,0 +1,305 @@
+use std::{fmt::Write, path::Path};
+
+use egui::{epaint::{CircleShape, PathShape, QuadraticBezierShape, RectShape, TextShape}, pos2, vec2, Color32, Pos2, Rect, Shape, Stroke};
+
+use crate::{objects::{Metadata, Objects}, screenshot::draw_headless};
+
+// space left around the edge of every page in pdf points
+pub const PAGE_MARGIN: f32 = 36.0;
+pub const TITLE_BLOCK_WIDTH: f32 = 240.0;
+pub const TITLE_BLOCK_HEIGHT: f32 = 72.0;
+
+// courier glyphs are all this many ems wide
+const COURIER_WIDTH: f32 = 0.6;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PageSize {
+    A4,
+    A3,
+    Letter,
+    Legal
+}
+
+impl PageSize {
+    // gets the portrait width and height in pdf points
+    pub fn dimensions(&self) -> (f32, f32) {
+        match self {
+            PageSize::A4 => (595.28, 841.89),
+            PageSize::A3 => (841.89, 1190.55),
+            PageSize::Letter => (612.0, 792.0),
+            PageSize::Legal => (612.0, 1008.0)
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PdfScale {
+    // shrink or grow the diagram onto a single page
+    Fit,
+    // pdf points per diagram point, tiling across pages when the diagram does not fit
+    Fixed(f32)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PdfOptions {
+    pub page_size: PageSize,
+    pub landscape: bool,
+    pub scale: PdfScale,
+    pub title_block: bool
+}
+
+impl Default for PdfOptions {
+    fn default() -> Self {
+        Self { page_size: PageSize::A4, landscape: true, scale: PdfScale::Fit, title_block: true }
+    }
+}
+
+impl PdfOptions {
+    // gets the page width and height in pdf points with orientation applied
+    pub fn page_dimensions(&self) -> (f32, f32) {
+        let (width, height) = self.page_size.dimensions();
+        if self.landscape { (height, width) } else { (width, height) }
+    }
+}
+
+// writes the given objects to a pdf file
+pub fn export_pdf(objects: &Objects, path: &Path, options: &PdfOptions) -> Result<(), String> {
+    if let PdfScale::Fixed(scale) = options.scale {
+        if scale <= 0.0 || !scale.is_finite() { return Err(format!("Invalid scale {scale}")) }
+    }
+
+    std::fs::write(path, to_pdf(objects, options))
+        .map_err(|err| format!("Could not write {}: {err}", path.display()))
+}
+
+// converts the given objects into a pdf document, using the same shapes the canvas draws
+pub fn to_pdf(objects: &Objects, options: &PdfOptions) -> Vec<u8> {
+    let frame = draw_headless(objects, 1.0);
+    let shapes = frame.flat_shapes();
+    let (page_width, page_height) = options.page_dimensions();
+
+    // get the part of the page the diagram can be drawn in, top down like the canvas
+    let reserved = if options.title_block { TITLE_BLOCK_HEIGHT + PAGE_MARGIN / 2.0 } else { 0.0 };
+    let area = Rect {
+        min: pos2(PAGE_MARGIN, PAGE_MARGIN),
+        max: pos2(page_width - PAGE_MARGIN, (page_height - PAGE_MARGIN - reserved).max(PAGE_MARGIN + 1.0))
+    };
+
+    // work out scale and how many pages are needed
+    let diagram = frame.clip.size();
+    let (scale, columns, rows) = match options.scale {
+        PdfScale::Fit => ((area.width() / diagram.x).min(area.height() / diagram.y), 1, 1),
+        PdfScale::Fixed(scale) => (
+            scale,
+            ((diagram.x * scale / area.width()).ceil() as usize).max(1),
+            ((diagram.y * scale / area.height()).ceil() as usize).max(1)
+        )
+    };
+
+    // center the diagram when it is on a single page
+    let centering = if columns == 1 && rows == 1 {
+        (area.size() - diagram * scale) / 2.0
+    } else { vec2(0.0, 0.0) };
+
+    let mut pages = Vec::new();
+    for row in 0 .. rows {
+        for column in 0 .. columns {
+            let offset = area.min + centering - vec2(column as f32 * area.width(), row as f32 * area.height());
+            let page = Page { height: page_height, offset, scale };
+
+            // clip to the drawing area so tiles do not overlap
+            let mut content = String::from("1 J 1 j\nq\n");
+            let _ = writeln!(content, "{} {} {} {} re W n", num(area.min.x), num(page_height - area.max.y), num(area.width()), num(area.height()));
+            shapes.iter().for_each(|shape| page.write_shape(&mut content, shape));
+            content.push_str("Q\n");
+
+            if options.title_block {
+                let sheet = pages.len() + 1;
+                write_title_block(&mut content, &objects.metadata, page_width, sheet, rows * columns);
+            }
+            pages.push(content);
+        }
+    }
+
+    write_document(&objects.metadata, page_width, page_height, pages)
+}
+
+// converts canvas coordinates to a position on a pdf page
+struct Page {
+    height: f32,
+    offset: Pos2,
+    scale: f32
+}
+
+impl Page {
+    fn point(&self, point: Pos2) -> String {
+        format!("{} {}", num(self.offset.x + point.x * self.scale), num(self.height - (self.offset.y + point.y * self.scale)))
+    }
+
+    fn write_shape(&self, content: &mut String, shape: &Shape) {
+        if shape.visual_bounding_rect().is_negative() { return }
+
+        match shape {
+            Shape::LineSegment { points, stroke } => {
+                self.write_path(content, points, false, Color32::TRANSPARENT, stroke);
+            },
+
+            Shape::Path(PathShape { points, closed, fill, stroke }) => {
+                self.write_path(content, points, *closed, if *closed { *fill } else { Color32::TRANSPARENT }, stroke);
+            },
+
+            Shape::Rect(RectShape { rect, fill, stroke, .. }) => {
+                let points = [rect.left_top(), rect.right_top(), rect.right_bottom(), rect.left_bottom()];
+                self.write_path(content, &points, true, *fill, stroke);
+            },
+
+            Shape::Circle(CircleShape { center, radius, fill, stroke }) => {
+                // four cubic curves make a close enough circle
+                let k = 0.5523 * radius;
+                let _ = writeln!(content, "{} m", self.point(*center + vec2(*radius, 0.0)));
+                [
+                    (vec2(*radius, k), vec2(k, *radius), vec2(0.0, *radius)),
+                    (vec2(-k, *radius), vec2(-radius, k), vec2(-radius, 0.0)),
+                    (vec2(-radius, -k), vec2(-k, -radius), vec2(0.0, -radius)),
+                    (vec2(k, -radius), vec2(*radius, -k), vec2(*radius, 0.0))
+                ].iter().for_each(|(a, b, c)| {
+                    let _ = writeln!(content, "{} {} {} c", self.point(*center + *a), self.point(*center + *b), self.point(*center + *c));
+                });
+                self.finish_path(content, true, *fill, stroke);
+            },
+
+            Shape::QuadraticBezier(QuadraticBezierShape { points, closed, fill, stroke }) => {
+                // pdf only has cubic curves so raise the degree
+                let a = points[0] + (points[1] - points[0]) * (2.0 / 3.0);
+                let b = points[2] + (points[1] - points[2]) * (2.0 / 3.0);
+                let _ = writeln!(content, "{} m", self.point(points[0]));
+                let _ = writeln!(content, "{} {} {} c", self.point(a), self.point(b), self.point(points[2]));
+                self.finish_path(content, *closed, *fill, stroke);
+            },
+
+            Shape::Text(text) => self.write_text(content, text),
+
+            _ => {}
+        }
+    }
+
+    fn write_path(&self, content: &mut String, points: &[Pos2], closed: bool, fill: Color32, stroke: &Stroke) {
+        let Some((first, rest)) = points.split_first() else { return };
+        let _ = writeln!(content, "{} m", self.point(*first));
+        rest.iter().for_each(|point| { let _ = writeln!(content, "{} l", self.point(*point)); });
+        self.finish_path(content, closed, fill, stroke);
+    }
+
+    fn finish_path(&self, content: &mut String, closed: bool, fill: Color32, stroke: &Stroke) {
+        let fill = visible(fill);
+        let stroke = if stroke.width > 0.0 { visible(stroke.color).map(|color| (color, stroke.width)) } else { None };
+        if closed { content.push_str("h\n"); }
+
+        if let Some(fill) = &fill { let _ = writeln!(content, "{} rg", fill); }
+        if let Some((color, width)) = &stroke { let _ = writeln!(content, "{} RG {} w", color, num(width * self.scale)); }
+        content.push_str(match (fill, stroke) {
+            (Some(_), Some(_)) => "B\n",
+            (Some(_), None) => "f\n",
+            (None, Some(_)) => "S\n",
+            (None, None) => "n\n"
+        });
+    }
+
+    // text is kept as real text so it can be searched and selected
+    fn write_text(&self, content: &mut String, text: &TextShape) {
+        let galley = &text.galley;
+        let Some(format) = galley.job.sections.first().map(|section| &section.format) else { return };
+        let Some(color) = visible(text.override_text_color.unwrap_or(format.color)) else { return };
+        let center = text.pos + galley.rect.center().to_vec2();
+
+        // canvas text is centered so line it up using courier widths
+        let size = format.font_id.size;
+        let start = center - vec2(galley.job.text.chars().count() as f32 * size * COURIER_WIDTH / 2.0, -size * 0.3);
+        let _ = writeln!(content, "BT /F1 {} Tf {} rg {} Td ({}) Tj ET", num(size * self.scale), color, self.point(start), escape(&galley.job.text));
+    }
+}
+
+fn write_title_block(content: &mut String, metadata: &Metadata, page_width: f32, sheet: usize, sheets: usize) {
+    let min = pos2(page_width - PAGE_MARGIN - TITLE_BLOCK_WIDTH, PAGE_MARGIN);
+    let row_height = TITLE_BLOCK_HEIGHT / 4.0;
+
+    // outline and rows
+    content.push_str("0 0 0 RG 1 w\n");
+    let _ = writeln!(content, "{} {} {} {} re S", num(min.x), num(min.y), num(TITLE_BLOCK_WIDTH), num(TITLE_BLOCK_HEIGHT));
+    (1 .. 4).for_each(|row| {
+        let y = min.y + row as f32 * row_height;
+        let _ = writeln!(content, "{} {} m {} {} l S", num(min.x), num(y), num(min.x + TITLE_BLOCK_WIDTH), num(y));
+    });
+
+    // text from the top row down
+    let lines = [
+        (11.0, metadata.title.clone()),
+        (9.0, format!("Author: {}", metadata.author)),
+        (9.0, format!("Date: {}  Rev: {}", metadata.date, metadata.revision)),
+        (9.0, format!("Sheet {sheet} of {sheets}"))
+    ];
+    lines.iter().enumerate().for_each(|(idx, (size, line))| {
+        let y = min.y + (3 - idx) as f32 * row_height + (row_height - size) / 2.0 + size * 0.2;
+        let _ = writeln!(content, "BT /F1 {} Tf 0 0 0 rg {} {} Td ({}) Tj ET", num(*size), num(min.x + 6.0), num(y), escape(line));
+    });
+}
+
+// assembles the pdf file itself
+fn write_document(metadata: &Metadata, page_width: f32, page_height: f32, pages: Vec<String>) -> Vec<u8> {
+    // catalog, page tree, font and info come first, then a page and content stream per page
+    let page_ids = (0 .. pages.len()).map(|idx| 5 + idx * 2).collect::<Vec<usize>>();
+    let mut objects = vec![
+        "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+        format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            page_ids.iter().map(|id| format!("{id} 0 R")).collect::<Vec<String>>().join(" "), pages.len()
+        ),
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Courier /Encoding /WinAnsiEncoding >>".to_string(),
+        format!(
+            "<< /Title ({}) /Author ({}) /Producer (Entity Relationship Editor) >>",
+            escape(&metadata.title), escape(&metadata.author)
+        )
+    ];
+    pages.iter().enumerate().for_each(|(idx, content)| {
+        objects.push(format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Resources << /Font << /F1 3 0 R >> >> /Contents {} 0 R >>",
+            num(page_width), num(page_height), page_ids[idx] + 1
+        ));
+        objects.push(format!("<< /Length {} >>\nstream\n{}endstream", content.len(), content));
+    });
+
+    let mut out = String::from("%PDF-1.4\n");
+    let mut offsets = Vec::new();
+    objects.iter().enumerate().for_each(|(idx, object)| {
+        offsets.push(out.len());
+        let _ = write!(out, "{} 0 obj\n{}\nendobj\n", idx + 1, object);
+    });
+
+    // cross reference table and trailer
+    let xref = out.len();
+    let _ = write!(out, "xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+    offsets.iter().for_each(|offset| { let _ = writeln!(out, "{offset:010} 00000 n "); });
+    let _ = write!(out, "trailer\n<< /Size {} /Root 1 0 R /Info 4 0 R >>\nstartxref\n{xref}\n%%EOF\n", objects.len() + 1);
+    out.into_bytes()
+}
+
+// gets a pdf color string if the color is not transparent
+fn visible(color: Color32) -> Option<String> {
+    let [r, g, b, a] = color.to_srgba_unmultiplied();
+    if a == 0 { return None }
+    Some(format!("{} {} {}", num(r as f32 / 255.0), num(g as f32 / 255.0), num(b as f32 / 255.0)))
+}
+
+fn num(value: f32) -> String {
+    let value = format!("{value:.3}");
+    value.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+// escapes a pdf string, only ascii is kept so offsets stay byte accurate
+fn escape(text: &str) -> String {
+    text.chars().map(|c| match c {
+        '(' | ')' | '\\' => format!("\\{c}"),
+        c if c.is_ascii() && !c.is_ascii_control() => c.to_string(),
+        _ => "?".to_string()
+    }).collect()
+}
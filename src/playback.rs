@@ -0,0 +1,238 @@
+use crate::bminustree::{TraceStep, TreeOptions, TreeRoot};
+
+// what a frame points out, nodes are indices into BTree::shapes
+#[derive(Clone, Debug, Default)]
+pub struct Marks {
+    pub nodes: Vec<usize>,
+    pub keys: Vec<String>
+}
+
+// the tree as it looks at one step of the playback
+#[derive(Clone, Debug)]
+pub struct Frame {
+    pub root: TreeRoot,
+    pub text: String,
+    pub marks: Marks
+}
+
+// replays inserting a sequence of keys into an empty tree, one step at a time
+#[derive(Clone, Debug)]
+pub struct Playback {
+    pub tree: u32,
+    pub sequence: String,
+    pub options: TreeOptions,
+    pub frames: Vec<Frame>,
+    pub current: usize,
+    pub playing: bool,
+    // seconds between steps while playing, and how long the current one has been shown
+    pub interval: f32,
+    pub elapsed: f32
+}
+
+impl Playback {
+    pub fn new(tree: u32, sequence: String, options: TreeOptions) -> Self {
+        Self { tree, sequence, options, frames: vec![], current: 0, playing: false, interval: 0.8, elapsed: 0.0 }
+    }
+
+    // inserts the keys one after another, keeping a frame for every step of every insert
+    pub fn record(&mut self, keys: &[String]) {
+        let mut root = TreeRoot::create(vec![], &self.options);
+        self.frames = vec![Frame { root: root.clone(), text: "empty tree".into(), marks: Marks::default() }];
+        keys.iter().enumerate().for_each(|(idx, key)| {
+            let before = root.clone();
+            let trace = root.insert(key);
+            let splits = trace.iter().any(|step| matches!(step, TraceStep::Split { .. } | TraceStep::SplitLeaf { .. }));
+            trace.iter().for_each(|step| {
+                // visits happen before anything changes, and a leaf that is about to split has no room to show the key yet
+                let shown = match step {
+                    TraceStep::Visit { .. } | TraceStep::Duplicate { .. } => &before,
+                    TraceStep::Insert { .. } if splits => &before,
+                    _ => &root
+                };
+                self.frames.push(Frame {
+                    root: shown.clone(),
+                    text: format!("{} of {}, insert {}: {step}", idx + 1, keys.len(), key.trim()),
+                    marks: marks(shown, step)
+                });
+            });
+        });
+        self.current = 0;
+        self.elapsed = 0.0;
+    }
+
+    pub fn frame(&self) -> Option<&Frame> { self.frames.get(self.current) }
+
+    pub fn at_end(&self) -> bool { self.current + 1 >= self.frames.len() }
+
+    pub fn forward(&mut self) {
+        if !self.at_end() { self.current += 1; }
+        self.elapsed = 0.0;
+    }
+
+    pub fn back(&mut self) {
+        self.current = self.current.saturating_sub(1);
+        self.elapsed = 0.0;
+    }
+
+    // moves on when enough time has passed, returning whether the frame changed
+    pub fn tick(&mut self, dt: f32) -> bool {
+        if !self.playing { return false }
+        self.elapsed += dt;
+        if self.elapsed < self.interval { return false }
+        self.forward();
+        if self.at_end() { self.playing = false; }
+        true
+    }
+}
+
+// index of the node holding a key, keys in leaves come first since b+ trees copy them into inner nodes
+fn node_with(root: &TreeRoot, key: &str) -> Option<usize> {
+    let nodes = root.nodes();
+    let holds = |keys: &[String]| keys.iter().any(|a| a.trim() == key.trim());
+    nodes.iter().position(|(keys, leaf)| *leaf && holds(keys)).or_else(|| nodes.iter().position(|(keys, _)| holds(keys)))
+}
+
+// index of the node with exactly these keys
+fn node_exactly(root: &TreeRoot, keys: &[String]) -> Option<usize> {
+    root.nodes().iter().position(|(node, _)| *node == keys)
+}
+
+// works out which nodes and keys a step is about
+fn marks(root: &TreeRoot, step: &TraceStep) -> Marks {
+    let (nodes, keys) = match step {
+        TraceStep::Visit { keys } => (vec![node_exactly(root, keys)], vec![]),
+        TraceStep::Found { key } | TraceStep::Duplicate { key } => (vec![node_with(root, key)], vec![key.clone()]),
+        // before the key is in the tree, the leaf is the one holding all its other keys
+        TraceStep::Insert { key, keys } => {
+            let leaf = node_with(root, key).or_else(|| root.nodes().iter().position(|(node, leaf)| {
+                *leaf && node.len() + 1 == keys.len() && node.iter().all(|a| keys.contains(a))
+            }));
+            (vec![leaf], vec![key.clone()])
+        },
+        TraceStep::NotFound { .. } => (vec![], vec![]),
+        // the two halves and the key that went up, a split leaf keeps its copy in the right half
+        TraceStep::Split { keys, promoted: middle } | TraceStep::SplitLeaf { keys, copied: middle } => {
+            let at = keys.iter().position(|key| key == middle).unwrap_or_default();
+            let right = if matches!(step, TraceStep::SplitLeaf { .. }) { at } else { at + 1 };
+            (vec![node_exactly(root, &keys[.. at]), node_exactly(root, &keys[right ..])], vec![middle.clone()])
+        },
+        TraceStep::Grow { key } => (vec![Some(0)], vec![key.clone()]),
+        TraceStep::Remove { keys, .. } => (vec![node_exactly(root, keys)], vec![]),
+        TraceStep::Replace { predecessor, .. } => (vec![node_with(root, predecessor)], vec![predecessor.clone()]),
+        TraceStep::Borrow { key, separator, .. } | TraceStep::BorrowLeaf { key, separator, .. } => {
+            (vec![node_with(root, key), node_with(root, separator)], vec![key.clone(), separator.clone()])
+        },
+        TraceStep::Merge { left, separator, right } => {
+            let merged = left.iter().chain([separator]).chain(right).cloned().collect::<Vec<String>>();
+            (vec![node_exactly(root, &merged)], vec![separator.clone()])
+        },
+        TraceStep::MergeLeaves { left, right, .. } => (vec![node_exactly(root, &[left.clone(), right.clone()].concat())], vec![]),
+        TraceStep::Shrink => (vec![Some(0)], vec![])
+    };
+    Marks { nodes: nodes.into_iter().flatten().collect(), keys }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bminustree::TreeKind;
+
+    fn options(kind: TreeKind) -> TreeOptions { TreeOptions { kind, order: 3, ..TreeOptions::default() } }
+
+    fn strings(keys: &[&str]) -> Vec<String> { keys.iter().map(|key| key.to_string()).collect() }
+
+    // a tree with keys inserted one at a time, and the steps of inserting the last of them
+    fn inserted(kind: TreeKind, keys: &[&str]) -> (TreeRoot, Vec<TraceStep>) {
+        let mut root = TreeRoot::create(vec![], &options(kind));
+        let trace = keys.iter().map(|key| root.insert(key)).last().unwrap_or_default();
+        (root, trace)
+    }
+
+    #[test]
+    fn recording_keeps_a_frame_for_every_step() {
+        let keys = strings(&["5", "1", "9", "3", "1"]);
+        let mut playback = Playback::new(1, keys.join(" "), options(TreeKind::BMinus));
+        playback.record(&keys);
+
+        let mut root = TreeRoot::create(vec![], &playback.options);
+        let steps = keys.iter().map(|key| root.insert(key)).collect::<Vec<Vec<TraceStep>>>();
+        assert_eq!(playback.frames.len(), steps.iter().map(|trace| trace.len()).sum::<usize>() + 1);
+        assert_eq!(playback.frames[0].text, "empty tree");
+        assert!(playback.frames[1].text.starts_with("1 of 5, insert 5: "), "{}", playback.frames[1].text);
+        assert_eq!(playback.frames.last().map(|frame| frame.root.all_keys()), Some(strings(&["1", "3", "5", "9"])));
+        assert_eq!(playback.current, 0);
+
+        // inserting 9 splits the leaf, so it only shows up once the split does
+        let frames = playback.frames.iter().skip(1).zip(steps.iter().flatten()).filter(|(frame, _)| frame.text.starts_with("3 of 5")).collect::<Vec<_>>();
+        let split = frames.iter().position(|(_, step)| matches!(step, TraceStep::Split { .. })).expect("inserting 9 splits");
+        frames.iter().enumerate().for_each(|(idx, (frame, step))| {
+            let keys = if idx < split { strings(&["1", "5"]) } else { strings(&["1", "5", "9"]) };
+            assert_eq!(frame.root.all_keys(), keys, "{step}");
+        });
+    }
+
+    #[test]
+    fn ticking_steps_forward_while_playing() {
+        let mut playback = Playback::new(1, "1 2 3".into(), options(TreeKind::BPlus));
+        playback.record(&strings(&["1", "2", "3"]));
+        assert!(!playback.tick(10.0));
+        assert_eq!(playback.current, 0);
+
+        playback.playing = true;
+        assert!(!playback.tick(0.5));
+        assert!(playback.tick(0.5));
+        assert_eq!((playback.current, playback.elapsed), (1, 0.0));
+
+        // playing stops on the last frame
+        while playback.tick(1.0) {}
+        assert!(playback.at_end() && !playback.playing);
+        assert_eq!(playback.current, playback.frames.len() - 1);
+    }
+
+    #[test]
+    fn visits_mark_the_node_with_exactly_those_keys() {
+        let (root, _) = inserted(TreeKind::BMinus, &["1", "2", "3", "4", "5"]);
+        let nodes = root.nodes();
+        nodes.iter().enumerate().for_each(|(idx, (keys, _))| {
+            assert_eq!(marks(&root, &TraceStep::Visit { keys: keys.to_vec() }).nodes, vec![idx]);
+        });
+        assert!(marks(&root, &TraceStep::Visit { keys: strings(&["7"]) }).nodes.is_empty());
+        assert!(marks(&root, &TraceStep::NotFound { key: "7".into() }).nodes.is_empty());
+    }
+
+    #[test]
+    fn found_keys_are_marked_in_their_leaf() {
+        // the separator 3 is copied into the root, but the key itself is in a leaf
+        let root = TreeRoot::create(strings(&["1", "2", "3", "4"]), &options(TreeKind::BPlus));
+        let found = marks(&root, &TraceStep::Found { key: "3".into() });
+        assert_eq!(found.keys, strings(&["3"]));
+        assert_eq!(found.nodes.len(), 1);
+        assert_eq!(root.nodes()[found.nodes[0]], (&strings(&["3", "4"])[..], true));
+    }
+
+    #[test]
+    fn inserts_mark_the_leaf_before_the_key_is_in_it() {
+        let root = TreeRoot::create(strings(&["1", "3"]), &options(TreeKind::BMinus));
+        let insert = marks(&root, &TraceStep::Insert { key: "2".into(), keys: strings(&["1", "2", "3"]) });
+        assert_eq!((insert.nodes, insert.keys), (vec![0], strings(&["2"])));
+    }
+
+    #[test]
+    fn splits_mark_both_halves() {
+        // the middle key goes up out of a b-tree node
+        let (root, trace) = inserted(TreeKind::BMinus, &["1", "2", "3"]);
+        let step = trace.iter().find(|step| matches!(step, TraceStep::Split { .. })).expect("the leaf splits");
+        let split = marks(&root, step);
+        assert_eq!(split.nodes.iter().map(|idx| root.nodes()[*idx].0.to_vec()).collect::<Vec<Vec<String>>>(), [strings(&["1"]), strings(&["3"])]);
+        assert_eq!(split.keys, strings(&["2"]));
+
+        // a b+ tree leaf keeps the copied key in its right half
+        let (root, trace) = inserted(TreeKind::BPlus, &["1", "2", "3"]);
+        let step = trace.iter().find(|step| matches!(step, TraceStep::SplitLeaf { .. })).expect("the leaf splits");
+        let split = marks(&root, step);
+        let halves = split.nodes.iter().map(|idx| root.nodes()[*idx].0.to_vec()).collect::<Vec<Vec<String>>>();
+        assert_eq!(halves.len(), 2);
+        assert!(halves[1].contains(&split.keys[0]), "{halves:?} {:?}", split.keys);
+        assert_eq!(halves.concat(), strings(&["1", "2", "3"]));
+    }
+}
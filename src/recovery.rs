@@ -0,0 +1,267 @@
+use std::{path::{Path, PathBuf}, sync::OnceLock, time::{Duration, SystemTime}};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{format, objects::Objects};
+
+// how often unsaved edits are copied to the recovery folder
+pub const AUTOSAVE_INTERVAL: Duration = Duration::from_secs(30);
+
+// how often a running editor marks itself as alive, copies of an editor that missed a few are offered back
+const HEARTBEAT: Duration = Duration::from_secs(10);
+const MISSED_HEARTBEATS: u32 = 3;
+
+// what is written to the recovery folder, the diagram is kept in the normal file format so older copies can be upgraded
+#[derive(Serialize, Deserialize)]
+struct RecoveryFile {
+    document: Option<PathBuf>,
+    // the editor that wrote it, none for copies from before it was recorded
+    #[serde(default)]
+    owner: Option<String>,
+    // seconds since the unix epoch
+    time: u64,
+    diagram: Value
+}
+
+// a recovery copy that is newer than the document it was made for
+pub struct Recovered {
+    // the recovery copy itself
+    pub path: PathBuf,
+    pub document: Option<PathBuf>,
+    pub time: SystemTime,
+    pub objects: Objects,
+    // what the copy changes compared to the document on disk
+    pub differences: Vec<String>
+}
+
+impl Recovered {
+    pub fn name(&self) -> String {
+        self.document.as_ref().map(|path| path.display().to_string()).unwrap_or("Untitled diagram".into())
+    }
+
+    pub fn age(&self) -> String {
+        let minutes = SystemTime::now().duration_since(self.time).unwrap_or_default().as_secs() / 60;
+        match minutes {
+            0 => "less than a minute ago".into(),
+            1 => "1 minute ago".into(),
+            minutes if minutes < 120 => format!("{minutes} minutes ago"),
+            minutes if minutes < 48 * 60 => format!("{} hours ago", minutes / 60),
+            minutes => format!("{} days ago", minutes / 60 / 24)
+        }
+    }
+}
+
+// the folder recovery copies are kept in
+pub struct Recovery {
+    pub dir: PathBuf
+}
+
+// where recovery copies are kept, next to other per user application data
+impl Default for Recovery {
+    fn default() -> Self {
+        let data = std::env::var_os("XDG_DATA_HOME").map(PathBuf::from)
+            .or_else(|| std::env::var_os("LOCALAPPDATA").map(PathBuf::from))
+            .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".local").join("share")))
+            .unwrap_or_else(std::env::temp_dir);
+        Self { dir: data.join("entity_relationship_drawer").join("recovery") }
+    }
+}
+
+// names this run of the editor, the start time keeps a reused process id from taking over an older runs copies
+fn instance() -> &'static str {
+    static INSTANCE: OnceLock<String> = OnceLock::new();
+    INSTANCE.get_or_init(|| {
+        let started = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap_or_default().as_millis();
+        format!("{}-{started}", std::process::id())
+    })
+}
+
+// a hash that stays the same between builds, so copies are found again after an update
+fn fnv(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| (hash ^ *byte as u64).wrapping_mul(0x100000001b3))
+}
+
+// what a copy in the recovery folder is to this editor
+enum Found {
+    Offer(Box<Recovered>),
+    // the document was saved since or nothing changed, so the copy is not needed
+    Stale,
+    // still being written by another editor, or not readable by this version, so it is left alone
+    Keep
+}
+
+impl Recovery {
+    // the recovery copy for a document, untitled diagrams get one per running editor
+    pub fn path_for(&self, document: Option<&Path>) -> PathBuf {
+        let name = match document {
+            Some(document) => {
+                let document = document.canonicalize().unwrap_or(document.to_path_buf());
+                format!("{:016x}.json", fnv(document.to_string_lossy().as_bytes()))
+            },
+            None => format!("untitled-{}.json", instance())
+        };
+        self.dir.join(name)
+    }
+
+    // marks this editor as alive for as long as it runs, so other editors leave its copies alone
+    pub fn keep_alive(&self) {
+        let (dir, path) = (self.dir.clone(), self.alive_path(instance()));
+        std::thread::spawn(move || loop {
+            let _ = std::fs::create_dir_all(&dir).and_then(|_| std::fs::write(&path, []));
+            std::thread::sleep(HEARTBEAT);
+        });
+    }
+
+    fn alive_path(&self, owner: &str) -> PathBuf {
+        self.dir.join(format!("{owner}.alive"))
+    }
+
+    // whether an editor marked itself alive lately
+    fn is_alive(&self, owner: &str) -> bool {
+        owner == instance() || std::fs::metadata(self.alive_path(owner))
+            .and_then(|metadata| metadata.modified())
+            .is_ok_and(|modified| modified.elapsed().unwrap_or_default() < HEARTBEAT * MISSED_HEARTBEATS)
+    }
+
+    // copies a diagram with unsaved edits to the recovery folder
+    pub fn write(&self, document: Option<&Path>, objects: &Objects) -> Result<(), String> {
+        let text = format::to_json(objects)?;
+        let diagram = serde_json::from_str(&text).map_err(|err| err.to_string())?;
+        let time = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap_or_default().as_secs();
+        let file = RecoveryFile { document: document.map(|path| path.canonicalize().unwrap_or(path.to_path_buf())), owner: Some(instance().to_string()), time, diagram };
+        let text = serde_json::to_string(&file).map_err(|err| err.to_string())?;
+
+        let path = self.path_for(document);
+        std::fs::create_dir_all(&self.dir)
+            .and_then(|_| format::write_atomic(&path, text.as_bytes()))
+            .map_err(|err| format!("Could not write {}: {err}", path.display()))
+    }
+
+    // forgets the recovery copy of a document, once it is saved or its edits are thrown away
+    pub fn remove(&self, document: Option<&Path>) {
+        let _ = std::fs::remove_file(self.path_for(document));
+    }
+
+    // recovery copies of editors that are no longer running that are newer than their documents, copies that are not needed are removed
+    pub fn pending(&self) -> Vec<Recovered> {
+        let Ok(entries) = std::fs::read_dir(&self.dir) else { return vec![] };
+        let paths = entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()).collect::<Vec<PathBuf>>();
+        let extension = |path: &Path, name: &str| path.extension().is_some_and(|extension| extension == name);
+
+        let mut pending = paths.iter()
+            .filter(|path| extension(path, "json"))
+            .filter_map(|path| match self.read(path) {
+                Found::Offer(recovered) => Some(*recovered),
+                Found::Stale => {
+                    let _ = std::fs::remove_file(path);
+                    None
+                },
+                Found::Keep => None
+            })
+            .collect::<Vec<Recovered>>();
+        pending.sort_by_key(|recovered| std::cmp::Reverse(recovered.time));
+
+        // editors that stopped marking themselves alive have ended
+        paths.iter()
+            .filter(|path| extension(path, "alive"))
+            .filter(|path| path.file_stem().and_then(|owner| owner.to_str()).is_some_and(|owner| !self.is_alive(owner)))
+            .for_each(|path| { let _ = std::fs::remove_file(path); });
+        pending
+    }
+
+    fn read(&self, path: &Path) -> Found {
+        let Some(file) = std::fs::read_to_string(path).ok().and_then(|text| serde_json::from_str::<RecoveryFile>(&text).ok()) else { return Found::Keep };
+        if file.owner.as_deref().is_some_and(|owner| self.is_alive(owner)) { return Found::Keep }
+        let time = SystemTime::UNIX_EPOCH + Duration::from_secs(file.time);
+        let Ok(objects) = format::from_json(&file.diagram.to_string()) else { return Found::Keep };
+
+        // compare with the document as it is on disk, a missing document means everything is new
+        let on_disk = match &file.document {
+            Some(document) => match std::fs::metadata(document).and_then(|metadata| metadata.modified()) {
+                Ok(modified) if modified >= time => return Found::Stale,
+                Ok(_) => format::load(document).unwrap_or_default(),
+                Err(_) => Objects::default()
+            },
+            None => Objects::default()
+        };
+        let differences = differences(&on_disk, &objects);
+        if differences.is_empty() { return Found::Stale }
+        Found::Offer(Box::new(Recovered { path: path.to_path_buf(), document: file.document, time, objects, differences }))
+    }
+}
+
+// a short list of what changed between two versions of a diagram, like "2 objects added"
+pub fn differences(old: &Objects, new: &Objects) -> Vec<String> {
+    let count = |count: usize, what: &str, how: &str| (count > 0).then(|| format!("{count} {what}{} {how}", if count == 1 { "" } else { "s" }));
+
+    // objects are matched by id, their sizes come from drawing so they are not compared
+    let added = new.objects.iter().filter(|a| !old.objects.iter().any(|b| b.id == a.id)).count();
+    let removed = old.objects.iter().filter(|a| !new.objects.iter().any(|b| b.id == a.id)).count();
+    let pairs = new.objects.iter().filter_map(|a| Some((old.objects.iter().find(|b| b.id == a.id)?, a))).collect::<Vec<_>>();
+    let changed = pairs.iter().filter(|(a, b)| a.name != b.name || a.object_type != b.object_type || a.pinned != b.pinned).count();
+    let moved = pairs.iter().filter(|(a, b)| a.x != b.x || a.y != b.y).count();
+
+    // links have no id, they are the same if they join the same objects the same way
+    let same_link = |a: &crate::objects::Link, b: &crate::objects::Link| a.a == b.a && a.b == b.b && a.minmax == b.minmax;
+    let links_added = new.links.iter().filter(|a| !old.links.iter().any(|b| same_link(a, b))).count();
+    let links_removed = old.links.iter().filter(|a| !new.links.iter().any(|b| same_link(a, b))).count();
+
+    let keys = |tree: &crate::bminustree::BTree| serde_json::to_value(&tree.root).ok();
+    let trees_added = new.trees.iter().filter(|a| !old.trees.iter().any(|b| b.id == a.id)).count();
+    let trees_removed = old.trees.iter().filter(|a| !new.trees.iter().any(|b| b.id == a.id)).count();
+    let tree_pairs = new.trees.iter().filter_map(|a| Some((old.trees.iter().find(|b| b.id == a.id)?, a))).collect::<Vec<_>>();
+    let trees_changed = tree_pairs.iter().filter(|(a, b)| keys(a) != keys(b)).count();
+    let trees_moved = tree_pairs.iter().filter(|(a, b)| a.x != b.x || a.y != b.y).count();
+
+    let metadata = |objects: &Objects| [&objects.metadata.title, &objects.metadata.author, &objects.metadata.date, &objects.metadata.revision].map(|field| field.clone());
+    let metadata_changed = (metadata(old) != metadata(new)).then(|| "diagram details changed".to_string());
+
+    [
+        count(added, "object", "added"),
+        count(removed, "object", "removed"),
+        count(changed, "object", "edited"),
+        count(moved, "object", "moved"),
+        count(links_added, "link", "added"),
+        count(links_removed, "link", "removed"),
+        count(trees_added, "tree", "added"),
+        count(trees_removed, "tree", "removed"),
+        count(trees_changed, "tree", "edited"),
+        count(trees_moved, "tree", "moved"),
+        metadata_changed
+    ].into_iter().flatten().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::objects::ObjectType;
+
+    #[test]
+    fn only_copies_of_ended_editors_are_offered() {
+        let recovery = Recovery { dir: std::env::temp_dir().join(format!("recovery-test-{}", std::process::id())) };
+        std::fs::create_dir_all(&recovery.dir).unwrap();
+
+        // a copy from an editor that is still running, one from an editor that ended, one that cannot be read and one from a newer version
+        let mut objects = Objects::default();
+        objects.add(ObjectType::Entity, 0.0, 0.0).name = "A".into();
+        let copy = |owner: &str| serde_json::to_string(&RecoveryFile {
+            document: None,
+            owner: Some(owner.into()),
+            time: 0,
+            diagram: serde_json::from_str(&format::to_json(&objects).unwrap()).unwrap()
+        }).unwrap();
+        std::fs::write(recovery.alive_path("running"), []).unwrap();
+        std::fs::write(recovery.dir.join("running.json"), copy("running")).unwrap();
+        std::fs::write(recovery.dir.join("ended.json"), copy("ended")).unwrap();
+        std::fs::write(recovery.dir.join("broken.json"), "{ not json").unwrap();
+        std::fs::write(recovery.dir.join("newer.json"), copy("ended").replace(&format!(r#""version":{}"#, format::VERSION), r#""version":99"#)).unwrap();
+
+        let pending = recovery.pending();
+        assert_eq!(pending.iter().map(|recovered| recovered.path.clone()).collect::<Vec<PathBuf>>(), [recovery.dir.join("ended.json")]);
+        assert_eq!(pending[0].differences, ["1 object added"]);
+        ["running.json", "ended.json", "broken.json", "newer.json", "running.alive"].iter().for_each(|name| assert!(recovery.dir.join(name).exists(), "{name} was removed"));
+
+        let _ = std::fs::remove_dir_all(&recovery.dir);
+    }
+}
@@ -0,0 +1,854 @@
+use std::fmt::Write;
+
+use crate::objects::{CardType, Object, ObjectType, Objects, Polymorph};
+
+// how polymorph specializations are turned into tables
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Inheritance {
+    // every type gets a table, subtypes share the supertypes key
+    ClassTable,
+    // subtypes are merged into the supertypes table with a discriminator
+    SingleTable,
+    // only subtypes get tables, each with a copy of the supertypes columns
+    ConcreteTable
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Dialect {
+    Postgres,
+    Sqlite,
+    MySql
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MappingOptions {
+    pub dialect: Dialect,
+    pub inheritance: Inheritance
+}
+
+impl Default for MappingOptions {
+    fn default() -> Self {
+        Self { dialect: Dialect::Postgres, inheritance: Inheritance::ClassTable }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ColumnType {
+    Text,
+    Integer,
+    Boolean,
+    // generated integer key for tables without an id attribute
+    Surrogate
+}
+
+impl ColumnType {
+    // gets the type a column referencing this one should have
+    fn referenced(&self) -> ColumnType {
+        match self {
+            ColumnType::Surrogate => ColumnType::Integer,
+            other => *other
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Column {
+    pub name: String,
+    pub column_type: ColumnType,
+    pub nullable: bool,
+    // the mapping rule that produced this column
+    pub rule: String
+}
+
+#[derive(Clone, Debug)]
+pub struct ForeignKey {
+    pub columns: Vec<String>,
+    pub table: String,
+    pub references: Vec<String>,
+    pub rule: String
+}
+
+#[derive(Clone, Debug)]
+pub struct Table {
+    pub name: String,
+    // the object this table was created from
+    pub source: u32,
+    pub rule: String,
+    pub columns: Vec<Column>,
+    pub primary_key: Vec<String>,
+    pub unique: Vec<Vec<String>>,
+    pub foreign_keys: Vec<ForeignKey>
+}
+
+impl Table {
+    fn has_column(&self, name: &str) -> bool { self.columns.iter().any(|column| column.name == name) }
+
+    // adds a column unless one with the same name exists
+    fn add_column(&mut self, column: Column) {
+        if !self.has_column(&column.name) { self.columns.push(column); }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Schema {
+    pub tables: Vec<Table>,
+    // anything in the diagram that could not be mapped or was dropped
+    pub notes: Vec<String>
+}
+
+// maps the given diagram to relational tables using the standard chen mapping rules
+pub fn map_objects(objects: &Objects, inheritance: Inheritance) -> Result<Schema, String> {
+    let mut mapper = Mapper { objects, schema: Schema::default() };
+    mapper.map_entities();
+    mapper.map_relationships();
+    mapper.map_polymorphs(inheritance);
+    mapper.check_clashes()?;
+    Ok(mapper.schema)
+}
+
+// converts a diagram name to a sql identifier
+pub fn sql_name(name: &str) -> String {
+    let name = name.trim().to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '_' })
+        .collect::<String>();
+    if name.is_empty() { "unnamed".into() } else { name }
+}
+
+fn is_entity(object: &Object) -> bool {
+    matches!(object.object_type, ObjectType::Entity | ObjectType::EntityDependent)
+}
+
+struct Mapper<'a> {
+    objects: &'a Objects,
+    schema: Schema
+}
+
+impl<'a> Mapper<'a> {
+    fn object(&self, id: u32) -> Option<&'a Object> { self.objects.objects.iter().find(|object| object.id == id) }
+
+    // gets every object linked to the given one in link order
+    fn neighbors(&self, id: u32) -> Vec<&'a Object> {
+        self.objects.links.iter()
+            .filter_map(|link| {
+                if link.a == id { self.object(link.b) }
+                else if link.b == id { self.object(link.a) }
+                else { None }
+            })
+            .collect()
+    }
+
+    fn table_mut(&mut self, source: u32) -> Option<&mut Table> { self.schema.tables.iter_mut().find(|table| table.source == source) }
+    fn table(&self, source: u32) -> Option<&Table> { self.schema.tables.iter().find(|table| table.source == source) }
+
+    // gets the stored attributes of an object as columns, with whether each is part of the key
+    fn attributes(&self, owner: &Object) -> Vec<(Column, bool)> {
+        let mut columns = Vec::new();
+        self.neighbors(owner.id).into_iter().for_each(|attribute| {
+            let ObjectType::Parameter { is_id } = attribute.object_type else { return };
+
+            // composite attributes are flattened into their parts
+            let parts = self.neighbors(attribute.id).into_iter()
+                .filter(|part| part.id != owner.id && matches!(part.object_type, ObjectType::Parameter { .. }))
+                .collect::<Vec<&Object>>();
+            if parts.is_empty() {
+                let rule = if is_id { format!("ID attribute {} becomes a primary key column", attribute.name) } else { format!("attribute {} becomes a column", attribute.name) };
+                columns.push((Column { name: sql_name(&attribute.name), column_type: ColumnType::Text, nullable: !is_id, rule }, is_id));
+            } else {
+                parts.iter().for_each(|part| {
+                    let part_is_id = is_id || matches!(part.object_type, ObjectType::Parameter { is_id: true });
+                    columns.push((Column {
+                        name: format!("{}_{}", sql_name(&attribute.name), sql_name(&part.name)),
+                        column_type: ColumnType::Text,
+                        nullable: !part_is_id,
+                        rule: format!("part {} of composite attribute {} becomes a column", part.name, attribute.name)
+                    }, part_is_id));
+                });
+            }
+        });
+        columns
+    }
+
+    // gets the owners of a weak entity with the identifying relationship to each
+    fn owners(&self, weak: &Object) -> Vec<(&'a Object, &'a Object)> {
+        if weak.object_type != ObjectType::EntityDependent { return vec![] }
+        self.neighbors(weak.id).into_iter()
+            .filter(|relationship| matches!(relationship.object_type, ObjectType::RelationshipDependent { .. }))
+            .flat_map(|relationship| {
+                self.neighbors(relationship.id).into_iter()
+                    .filter(|owner| owner.id != weak.id && is_entity(owner))
+                    .map(move |owner| (relationship, owner))
+            })
+            .collect()
+    }
+
+    // gets the supertype of the given entity through a disjoint or overlapping polymorph
+    fn supertype(&self, id: u32) -> Option<&'a Object> {
+        self.neighbors(id).into_iter()
+            .filter(|polymorph| matches!(polymorph.object_type, ObjectType::Polymorph { poly: Polymorph::Disjoint | Polymorph::Overlapping }))
+            .find_map(|polymorph| {
+                let members = self.neighbors(polymorph.id).into_iter().filter(|a| is_entity(a)).collect::<Vec<&Object>>();
+                match members.first() {
+                    Some(first) if first.id != id => Some(*first),
+                    _ => None
+                }
+            })
+    }
+
+    // gets the primary key columns of an entity, including keys borrowed from owners or supertypes
+    fn key_columns(&self, entity: &Object, visiting: &mut Vec<u32>) -> Vec<Column> {
+        if visiting.contains(&entity.id) { return vec![] }
+        visiting.push(entity.id);
+
+        let mut keys = self.attributes(entity).into_iter()
+            .filter(|(_, is_key)| *is_key)
+            .map(|(column, _)| column)
+            .collect::<Vec<Column>>();
+
+        // weak entities are identified through their owners
+        let owners = self.owners(entity);
+        if !owners.is_empty() {
+            let mut owner_keys = Vec::new();
+            owners.iter().for_each(|(relationship, owner)| {
+                self.key_columns(owner, visiting).into_iter().for_each(|column| {
+                    owner_keys.push(Column {
+                        name: format!("{}_{}", sql_name(&owner.name), column.name),
+                        column_type: column.column_type.referenced(),
+                        nullable: false,
+                        rule: format!("key of owner {} through identifying relationship {}", owner.name, relationship.name)
+                    });
+                });
+            });
+            owner_keys.extend(keys);
+            keys = owner_keys;
+        } else if keys.is_empty() {
+            // subtypes without their own id share the supertypes key
+            if let Some(supertype) = self.supertype(entity.id) {
+                keys = self.key_columns(supertype, visiting).into_iter()
+                    .map(|column| Column {
+                        column_type: column.column_type.referenced(),
+                        rule: format!("key inherited from supertype {}", supertype.name),
+                        ..column
+                    })
+                    .collect();
+            }
+        }
+
+        if keys.is_empty() {
+            keys.push(Column { name: "id".into(), column_type: ColumnType::Surrogate, nullable: false, rule: format!("{} has no ID attribute, a surrogate key is added", entity.name) });
+        }
+
+        visiting.pop();
+        keys
+    }
+
+    fn keys_of(&self, entity: &Object) -> Vec<Column> { self.key_columns(entity, &mut Vec::new()) }
+
+    // every entity becomes a table
+    fn map_entities(&mut self) {
+        let objects = self.objects;
+        objects.objects.iter().filter(|a| is_entity(a)).for_each(|entity| {
+            let keys = self.keys_of(entity);
+            let mut table = Table {
+                name: sql_name(&entity.name),
+                source: entity.id,
+                rule: match entity.object_type {
+                    ObjectType::EntityDependent => format!("weak entity {} becomes a table keyed by its owner", entity.name),
+                    _ => format!("entity {} becomes a table", entity.name)
+                },
+                primary_key: keys.iter().map(|column| column.name.clone()).collect(),
+                columns: keys,
+                unique: vec![],
+                foreign_keys: vec![]
+            };
+
+            // remaining attributes
+            self.attributes(entity).into_iter()
+                .filter(|(_, is_key)| !*is_key)
+                .for_each(|(column, _)| table.add_column(column));
+            self.neighbors(entity.id).iter()
+                .filter(|a| matches!(a.object_type, ObjectType::FunctionParameter { .. }))
+                .for_each(|derived| self.schema.notes.push(format!("derived attribute {} of {} is not stored", derived.name, entity.name)));
+
+            // weak entities reference their owners
+            self.owners(entity).iter().for_each(|(relationship, owner)| {
+                let references = self.keys_of(owner).into_iter().map(|column| column.name).collect::<Vec<String>>();
+                table.foreign_keys.push(ForeignKey {
+                    columns: references.iter().map(|column| format!("{}_{}", sql_name(&owner.name), column)).collect(),
+                    table: sql_name(&owner.name),
+                    references,
+                    rule: format!("identifying relationship {}", relationship.name)
+                });
+            });
+
+            self.schema.tables.push(table);
+        });
+    }
+
+    // relationships become foreign keys or junction tables depending on their cardinality
+    fn map_relationships(&mut self) {
+        let objects = self.objects;
+        objects.objects.iter().for_each(|relationship| {
+            let card = match &relationship.object_type {
+                ObjectType::Relationship { card } |
+                ObjectType::RelationshipDependent { card } => card.clone(),
+                _ => return
+            };
+            let one_to_one = card == CardType::OneToOne;
+            let entities = self.neighbors(relationship.id).into_iter().filter(|a| is_entity(a)).collect::<Vec<&Object>>();
+            let attributes = self.attributes(relationship);
+
+            // identifying relationships were handled with their weak entity
+            let weak = entities.iter().find(|entity| {
+                matches!(relationship.object_type, ObjectType::RelationshipDependent { .. }) && entity.object_type == ObjectType::EntityDependent
+            });
+            if let Some(weak) = weak {
+                let weak_id = weak.id;
+                if let Some(table) = self.table_mut(weak_id) {
+                    attributes.into_iter().for_each(|(column, _)| table.add_column(Column { nullable: true, ..column }));
+                }
+                return
+            }
+
+            match (entities.as_slice(), card) {
+                ([], _) | ([_], _) => {
+                    self.schema.notes.push(format!("relationship {} connects fewer than two entities and was skipped", relationship.name));
+                },
+
+                // the many side or the second one to one side gets the foreign key
+                ([one, many], CardType::OneToMany) |
+                ([many, one], CardType::ManyToOne) |
+                ([one, many], CardType::OneToOne) => {
+                    let references = self.keys_of(one);
+                    let Some(table) = self.table_mut(many.id) else { return };
+
+                    let rule = if one_to_one {
+                        format!("one to one relationship {} becomes a unique foreign key", relationship.name)
+                    } else {
+                        format!("one to many relationship {} becomes a foreign key on the many side", relationship.name)
+                    };
+                    let columns = references.iter().map(|column| {
+                        let name = format!("{}_{}", sql_name(&one.name), column.name);
+                        let name = if table.has_column(&name) { format!("{}_{}", sql_name(&relationship.name), name) } else { name };
+                        Column { name, column_type: column.column_type.referenced(), nullable: true, rule: rule.clone() }
+                    }).collect::<Vec<Column>>();
+
+                    table.foreign_keys.push(ForeignKey {
+                        columns: columns.iter().map(|column| column.name.clone()).collect(),
+                        table: sql_name(&one.name),
+                        references: references.iter().map(|column| column.name.clone()).collect(),
+                        rule: rule.clone()
+                    });
+                    if one_to_one { table.unique.push(columns.iter().map(|column| column.name.clone()).collect()); }
+                    columns.into_iter().for_each(|column| table.add_column(column));
+
+                    // relationship attributes follow the foreign key
+                    attributes.into_iter().for_each(|(column, _)| table.add_column(Column {
+                        nullable: true,
+                        rule: format!("attribute of relationship {} moves with its foreign key", relationship.name),
+                        ..column
+                    }));
+                },
+
+                // many to many and higher degree relationships get their own table
+                (entities, _) => {
+                    let rule = if entities.len() == 2 {
+                        format!("many to many relationship {} becomes a junction table", relationship.name)
+                    } else {
+                        format!("{} way relationship {} becomes a junction table", entities.len(), relationship.name)
+                    };
+                    let mut table = Table {
+                        name: sql_name(&relationship.name),
+                        source: relationship.id,
+                        rule: rule.clone(),
+                        columns: vec![],
+                        primary_key: vec![],
+                        unique: vec![],
+                        foreign_keys: vec![]
+                    };
+
+                    entities.iter().for_each(|entity| {
+                        let references = self.keys_of(entity);
+                        let columns = references.iter().map(|column| Column {
+                            name: format!("{}_{}", sql_name(&entity.name), column.name),
+                            column_type: column.column_type.referenced(),
+                            nullable: false,
+                            rule: format!("key of {} in junction table", entity.name)
+                        }).collect::<Vec<Column>>();
+                        table.foreign_keys.push(ForeignKey {
+                            columns: columns.iter().map(|column| column.name.clone()).collect(),
+                            table: sql_name(&entity.name),
+                            references: references.into_iter().map(|column| column.name).collect(),
+                            rule: rule.clone()
+                        });
+                        table.primary_key.extend(columns.iter().map(|column| column.name.clone()));
+                        columns.into_iter().for_each(|column| table.add_column(column));
+                    });
+
+                    attributes.into_iter().for_each(|(column, is_key)| {
+                        if is_key { table.primary_key.push(column.name.clone()); }
+                        table.add_column(Column { rule: format!("attribute of relationship {}", relationship.name), ..column });
+                    });
+                    self.schema.tables.push(table);
+                }
+            }
+        });
+    }
+
+    // polymorphs become the chosen inheritance strategy
+    fn map_polymorphs(&mut self, inheritance: Inheritance) {
+        let objects = self.objects;
+        objects.objects.iter().for_each(|polymorph| {
+            let ObjectType::Polymorph { poly } = &polymorph.object_type else { return };
+            let members = self.neighbors(polymorph.id).into_iter().filter(|a| is_entity(a)).collect::<Vec<&Object>>();
+            let Some((first, rest)) = members.split_first() else {
+                self.schema.notes.push(format!("polymorph {} is not connected to any entities", polymorph.name));
+                return
+            };
+            if rest.is_empty() {
+                self.schema.notes.push(format!("polymorph {} has no subtypes", polymorph.name));
+                return
+            }
+
+            match poly {
+                // a union category is referenced from each of its supertypes
+                Polymorph::Union => {
+                    let category = *first;
+                    let references = self.keys_of(category);
+                    rest.iter().for_each(|supertype| {
+                        let Some(table) = self.table_mut(supertype.id) else { return };
+                        let rule = format!("{} is a member of union category {}", supertype.name, category.name);
+                        let columns = references.iter().map(|column| Column {
+                            name: format!("{}_{}", sql_name(&category.name), column.name),
+                            column_type: column.column_type.referenced(),
+                            nullable: true,
+                            rule: rule.clone()
+                        }).collect::<Vec<Column>>();
+                        table.foreign_keys.push(ForeignKey {
+                            columns: columns.iter().map(|column| column.name.clone()).collect(),
+                            table: sql_name(&category.name),
+                            references: references.iter().map(|column| column.name.clone()).collect(),
+                            rule
+                        });
+                        columns.into_iter().for_each(|column| table.add_column(column));
+                    });
+                },
+
+                Polymorph::Disjoint |
+                Polymorph::Overlapping => match inheritance {
+                    Inheritance::ClassTable => self.class_table(first, rest),
+                    Inheritance::SingleTable => self.single_table(first, rest, *poly == Polymorph::Overlapping),
+                    Inheritance::ConcreteTable => self.concrete_table(first, rest)
+                }
+            }
+        });
+    }
+
+    fn class_table(&mut self, supertype: &Object, subtypes: &[&Object]) {
+        let references = self.keys_of(supertype);
+        subtypes.iter().for_each(|subtype| {
+            let keys = self.keys_of(subtype);
+            let Some(table) = self.table_mut(subtype.id) else { return };
+            let rule = format!("subtype {} references supertype {} with table per type inheritance", subtype.name, supertype.name);
+
+            // reuse the shared key when the subtype has no id of its own
+            let shared = keys.iter().map(|column| &column.name).eq(references.iter().map(|column| &column.name));
+            let columns = if shared {
+                keys.iter().map(|column| column.name.clone()).collect::<Vec<String>>()
+            } else {
+                references.iter().map(|column| {
+                    let name = format!("{}_{}", sql_name(&supertype.name), column.name);
+                    table.add_column(Column { name: name.clone(), column_type: column.column_type.referenced(), nullable: false, rule: rule.clone() });
+                    name
+                }).collect()
+            };
+            table.foreign_keys.push(ForeignKey {
+                columns,
+                table: sql_name(&supertype.name),
+                references: references.iter().map(|column| column.name.clone()).collect(),
+                rule
+            });
+        });
+    }
+
+    fn single_table(&mut self, supertype: &Object, subtypes: &[&Object], overlapping: bool) {
+        let names = subtypes.iter().map(|subtype| subtype.name.clone()).collect::<Vec<String>>().join(", ");
+        let super_name = sql_name(&supertype.name);
+
+        subtypes.iter().for_each(|subtype| {
+            let Some(idx) = self.schema.tables.iter().position(|table| table.source == subtype.id) else { return };
+            let merged = self.schema.tables.remove(idx);
+            let Some(table) = self.table_mut(supertype.id) else { return };
+
+            // subtype columns become optional columns on the supertype
+            merged.columns.into_iter().for_each(|column| table.add_column(Column {
+                nullable: true,
+                rule: format!("column of subtype {} merged into {}: {}", subtype.name, supertype.name, column.rule),
+                ..column
+            }));
+            table.foreign_keys.extend(merged.foreign_keys);
+            table.unique.extend(merged.unique);
+
+            if overlapping {
+                table.add_column(Column {
+                    name: format!("is_{}", merged.name),
+                    column_type: ColumnType::Boolean,
+                    nullable: false,
+                    rule: format!("flag for overlapping subtype {}", subtype.name)
+                });
+            }
+
+            // anything pointing at the subtype now points at the merged table
+            self.schema.tables.iter_mut()
+                .flat_map(|table| table.foreign_keys.iter_mut())
+                .filter(|key| key.table == merged.name)
+                .for_each(|key| key.table = super_name.clone());
+        });
+
+        if !overlapping {
+            if let Some(table) = self.table_mut(supertype.id) {
+                table.add_column(Column {
+                    name: format!("{}_type", super_name),
+                    column_type: ColumnType::Text,
+                    nullable: true,
+                    rule: format!("discriminator for disjoint subtypes {names}")
+                });
+            }
+        }
+    }
+
+    // tables are created and referenced by name, so two tables with one name cannot be mapped
+    fn check_clashes(&self) -> Result<(), String> {
+        let name = |table: &Table| self.object(table.source).map(|object| object.name.clone()).unwrap_or_default();
+        let clashes = self.schema.tables.iter().enumerate()
+            .filter_map(|(idx, table)| {
+                let first = self.schema.tables[.. idx].iter().find(|other| other.name == table.name)?;
+                Some(format!("{} and {} would both become table {}, give them different names", name(first), name(table), table.name))
+            })
+            .collect::<Vec<String>>();
+        if clashes.is_empty() { Ok(()) } else { Err(clashes.join("\n")) }
+    }
+
+    fn concrete_table(&mut self, supertype: &Object, subtypes: &[&Object]) {
+        let Some(inherited) = self.table(supertype.id).cloned() else { return };
+
+        subtypes.iter().for_each(|subtype| {
+            let Some(table) = self.table_mut(subtype.id) else { return };
+            inherited.columns.iter().for_each(|column| table.add_column(Column {
+                rule: format!("column inherited from supertype {}: {}", supertype.name, column.rule),
+                ..column.clone()
+            }));
+            table.foreign_keys.extend(inherited.foreign_keys.iter().cloned());
+        });
+
+        // the supertype no longer has a table so nothing can reference it
+        self.schema.tables.retain(|table| table.source != supertype.id);
+        self.schema.tables.iter_mut().for_each(|table| {
+            let name = table.name.clone();
+            table.foreign_keys.retain(|key| {
+                if key.table != inherited.name { return true }
+                self.schema.notes.push(format!("foreign key from {} to {} dropped, {} has no table with table per concrete class inheritance", name, inherited.name, supertype.name));
+                false
+            });
+        });
+    }
+}
+
+impl Dialect {
+    fn quote(&self, name: &str) -> String {
+        match self {
+            Dialect::Postgres | Dialect::Sqlite => format!("\"{}\"", name.replace('"', "\"\"")),
+            Dialect::MySql => format!("`{}`", name.replace('`', "``"))
+        }
+    }
+
+    fn type_name(&self, column_type: ColumnType) -> &'static str {
+        match (self, column_type) {
+            (Dialect::Postgres, ColumnType::Text) => "TEXT",
+            (Dialect::Postgres, ColumnType::Integer) => "INTEGER",
+            (Dialect::Postgres, ColumnType::Boolean) => "BOOLEAN",
+            (Dialect::Postgres, ColumnType::Surrogate) => "INTEGER GENERATED BY DEFAULT AS IDENTITY",
+            (Dialect::Sqlite, ColumnType::Text) => "TEXT",
+            (Dialect::Sqlite, ColumnType::Integer | ColumnType::Boolean | ColumnType::Surrogate) => "INTEGER",
+            (Dialect::MySql, ColumnType::Text) => "VARCHAR(255)",
+            (Dialect::MySql, ColumnType::Integer) => "INT",
+            (Dialect::MySql, ColumnType::Boolean) => "BOOLEAN",
+            (Dialect::MySql, ColumnType::Surrogate) => "INT AUTO_INCREMENT"
+        }
+    }
+
+    fn quote_list(&self, names: &[String]) -> String {
+        names.iter().map(|name| self.quote(name)).collect::<Vec<String>>().join(", ")
+    }
+}
+
+impl Schema {
+    // generates create table statements, ordered so referenced tables come first
+    pub fn to_sql(&self, dialect: Dialect) -> String {
+        let order = self.creation_order();
+        let mut sql = String::new();
+        let mut deferred = Vec::new();
+
+        order.iter().enumerate().for_each(|(position, idx)| {
+            let table = &self.tables[*idx];
+            let _ = writeln!(sql, "-- {}", table.rule);
+            let _ = writeln!(sql, "CREATE TABLE {} (", dialect.quote(&table.name));
+
+            let mut lines = table.columns.iter()
+                .map(|column| format!(
+                    "    {} {}{}",
+                    dialect.quote(&column.name), dialect.type_name(column.column_type), if column.nullable { "" } else { " NOT NULL" }
+                ))
+                .collect::<Vec<String>>();
+            if !table.primary_key.is_empty() {
+                lines.push(format!("    PRIMARY KEY ({})", dialect.quote_list(&table.primary_key)));
+            }
+            table.unique.iter().for_each(|unique| lines.push(format!("    UNIQUE ({})", dialect.quote_list(unique))));
+
+            // keys to tables created later are added afterwards, sqlite does not check order so keeps them inline
+            table.foreign_keys.iter().for_each(|key| {
+                let target = self.tables.iter().position(|table| table.name == key.table);
+                let later = target.is_some_and(|target| order.iter().position(|a| *a == target).unwrap_or(0) > position);
+                let constraint = format!(
+                    "FOREIGN KEY ({}) REFERENCES {} ({})",
+                    dialect.quote_list(&key.columns), dialect.quote(&key.table), dialect.quote_list(&key.references)
+                );
+                if later && dialect != Dialect::Sqlite { deferred.push((table.name.clone(), constraint)); }
+                else { lines.push(format!("    {constraint}")); }
+            });
+
+            let _ = writeln!(sql, "{}", lines.join(",\n"));
+            let _ = writeln!(sql, "){};\n", if dialect == Dialect::MySql { " ENGINE=InnoDB" } else { "" });
+        });
+
+        deferred.iter().for_each(|(table, constraint)| {
+            let _ = writeln!(sql, "ALTER TABLE {} ADD {};", dialect.quote(table), constraint);
+        });
+        sql
+    }
+
+    // describes which rule produced each table and column
+    pub fn report(&self) -> String {
+        let mut report = String::new();
+        self.tables.iter().for_each(|table| {
+            let _ = writeln!(report, "{}: {}", table.name, table.rule);
+            table.columns.iter().for_each(|column| {
+                let _ = writeln!(report, "    {}: {}", column.name, column.rule);
+            });
+            table.foreign_keys.iter().for_each(|key| {
+                let _ = writeln!(report, "    ({}) -> {}: {}", key.columns.join(", "), key.table, key.rule);
+            });
+        });
+        if !self.notes.is_empty() {
+            report.push_str("notes:\n");
+            self.notes.iter().for_each(|note| { let _ = writeln!(report, "    {note}"); });
+        }
+        report
+    }
+
+    // orders tables depth first so a table comes after the tables it references where possible
+    fn creation_order(&self) -> Vec<usize> {
+        fn visit(schema: &Schema, idx: usize, visiting: &mut Vec<usize>, order: &mut Vec<usize>) {
+            if order.contains(&idx) || visiting.contains(&idx) { return }
+            visiting.push(idx);
+            schema.tables[idx].foreign_keys.iter().for_each(|key| {
+                if let Some(target) = schema.tables.iter().position(|table| table.name == key.table) {
+                    visit(schema, target, visiting, order);
+                }
+            });
+            visiting.pop();
+            order.push(idx);
+        }
+
+        let mut order = Vec::new();
+        (0 .. self.tables.len()).for_each(|idx| visit(self, idx, &mut Vec::new(), &mut order));
+        order
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dsl;
+
+    fn map(text: &str, inheritance: Inheritance) -> Schema { map_objects(&dsl::parse(text).unwrap(), inheritance).unwrap() }
+
+    fn table<'a>(schema: &'a Schema, name: &str) -> &'a Table {
+        schema.tables.iter().find(|table| table.name == name).unwrap_or_else(|| panic!("no table {name} in {}", schema.report()))
+    }
+
+    fn columns(table: &Table) -> Vec<&str> { table.columns.iter().map(|column| column.name.as_str()).collect() }
+
+    fn keys(table: &Table) -> Vec<(Vec<String>, &str, Vec<String>)> {
+        table.foreign_keys.iter().map(|key| (key.columns.clone(), key.table.as_str(), key.references.clone())).collect()
+    }
+
+    fn strings(names: &[&str]) -> Vec<String> { names.iter().map(|name| name.to_string()).collect() }
+
+    const PEOPLE: &str = "
+        entity PERSON { attribute pid id attribute name }
+        polymorph PERSON_poly disjoint
+        entity STUDENT { attribute gpa }
+        entity EMPLOYEE { attribute salary }
+        link PERSON -- PERSON_poly
+        link PERSON_poly -- STUDENT
+        link PERSON_poly -- EMPLOYEE
+    ";
+
+    #[test]
+    fn entities_are_keyed_by_their_ids() {
+        let schema = map("entity PERSON { attribute pid id attribute name attribute address { attribute city } } entity NOTE", Inheritance::ClassTable);
+        let person = table(&schema, "person");
+        assert_eq!(person.primary_key, strings(&["pid"]));
+        assert_eq!(columns(person), vec!["pid", "name", "address_city"]);
+        assert!(!person.columns[0].nullable && person.columns[1].nullable);
+
+        // without an id a surrogate key is added
+        let note = table(&schema, "note");
+        assert_eq!(note.primary_key, strings(&["id"]));
+        assert_eq!(note.columns[0].column_type, ColumnType::Surrogate);
+    }
+
+    #[test]
+    fn weak_entities_are_keyed_by_their_owner() {
+        let schema = map("
+            entity BUILDING { attribute bid id }
+            weak entity ROOM { attribute number id attribute seats }
+            weak relationship HAS 1:N
+            link BUILDING -- HAS
+            link HAS -- ROOM
+        ", Inheritance::ClassTable);
+        let room = table(&schema, "room");
+        assert_eq!(room.primary_key, strings(&["building_bid", "number"]));
+        assert_eq!(keys(room), vec![(strings(&["building_bid"]), "building", strings(&["bid"]))]);
+        assert!(!schema.tables.iter().any(|table| table.name == "has"));
+
+        // the owner has to exist before the weak entity references it
+        let sql = schema.to_sql(Dialect::Postgres);
+        assert!(sql.find("CREATE TABLE \"building\"") < sql.find("CREATE TABLE \"room\""));
+    }
+
+    #[test]
+    fn one_to_many_puts_the_key_on_the_many_side() {
+        [("1:N", "DEPT -- WORKS", "WORKS -- EMP"), ("N:1", "EMP -- WORKS", "WORKS -- DEPT")].iter().for_each(|(card, a, b)| {
+            let schema = map(&format!("
+                entity DEPT {{ attribute did id }}
+                entity EMP {{ attribute eid id }}
+                relationship WORKS {card} {{ attribute since }}
+                link {a}
+                link {b}
+            "), Inheritance::ClassTable);
+            let emp = table(&schema, "emp");
+            assert_eq!(keys(emp), vec![(strings(&["dept_did"]), "dept", strings(&["did"]))], "{card}");
+            assert_eq!(columns(emp), vec!["eid", "dept_did", "since"], "{card}");
+            assert!(emp.unique.is_empty());
+            assert!(table(&schema, "dept").foreign_keys.is_empty());
+            assert_eq!(schema.tables.len(), 2);
+        });
+    }
+
+    #[test]
+    fn one_to_one_is_a_unique_key() {
+        let schema = map("
+            entity EMP { attribute eid id }
+            entity DEPT { attribute did id }
+            relationship MANAGES 1:1
+            link EMP -- MANAGES
+            link MANAGES -- DEPT
+        ", Inheritance::ClassTable);
+        let dept = table(&schema, "dept");
+        assert_eq!(keys(dept), vec![(strings(&["emp_eid"]), "emp", strings(&["eid"]))]);
+        assert_eq!(dept.unique, vec![strings(&["emp_eid"])]);
+    }
+
+    #[test]
+    fn many_to_many_becomes_a_junction_table() {
+        let schema = map("
+            entity STUDENT { attribute sid id }
+            entity COURSE { attribute cid id }
+            relationship TAKES M:N { attribute grade }
+            link STUDENT -- TAKES
+            link TAKES -- COURSE
+        ", Inheritance::ClassTable);
+        let takes = table(&schema, "takes");
+        assert_eq!(takes.primary_key, strings(&["student_sid", "course_cid"]));
+        assert_eq!(columns(takes), vec!["student_sid", "course_cid", "grade"]);
+        assert_eq!(keys(takes), vec![
+            (strings(&["student_sid"]), "student", strings(&["sid"])),
+            (strings(&["course_cid"]), "course", strings(&["cid"]))
+        ]);
+        assert!(table(&schema, "student").foreign_keys.is_empty() && table(&schema, "course").foreign_keys.is_empty());
+    }
+
+    #[test]
+    fn relationships_without_two_entities_are_noted() {
+        let schema = map("entity A relationship R 1:N link A -- R", Inheritance::ClassTable);
+        assert_eq!(schema.tables.len(), 1);
+        assert!(schema.report().contains("relationship R connects fewer than two entities"));
+    }
+
+    #[test]
+    fn class_table_inheritance_shares_the_supertype_key() {
+        let schema = map(PEOPLE, Inheritance::ClassTable);
+        assert_eq!(schema.tables.len(), 3);
+        ["student", "employee"].iter().for_each(|name| {
+            let subtype = table(&schema, name);
+            assert_eq!(subtype.primary_key, strings(&["pid"]));
+            assert_eq!(keys(subtype), vec![(strings(&["pid"]), "person", strings(&["pid"]))]);
+        });
+        assert_eq!(columns(table(&schema, "person")), vec!["pid", "name"]);
+    }
+
+    #[test]
+    fn single_table_inheritance_merges_subtypes() {
+        let schema = map(PEOPLE, Inheritance::SingleTable);
+        assert_eq!(schema.tables.len(), 1);
+        let person = table(&schema, "person");
+        assert_eq!(columns(person), vec!["pid", "name", "gpa", "salary", "person_type"]);
+        assert!(person.columns[2 ..].iter().all(|column| column.nullable));
+
+        // overlapping subtypes get a flag each instead of one discriminator
+        let schema = map(&PEOPLE.replace("disjoint", "overlapping"), Inheritance::SingleTable);
+        assert_eq!(columns(table(&schema, "person")), vec!["pid", "name", "gpa", "is_student", "salary", "is_employee"]);
+    }
+
+    #[test]
+    fn concrete_table_inheritance_copies_the_supertype() {
+        let text = format!("{PEOPLE}
+            entity CLUB {{ attribute cid id }} relationship LEADS 1:N link CLUB -- LEADS link LEADS -- PERSON
+            entity CAR {{ attribute vin id }} relationship OWNS 1:N link PERSON -- OWNS link OWNS -- CAR
+        ");
+        let schema = map(&text, Inheritance::ConcreteTable);
+        assert!(!schema.tables.iter().any(|table| table.name == "person"));
+
+        // columns and keys of the supertype are copied into every subtype
+        ["student", "employee"].iter().for_each(|name| {
+            let subtype = table(&schema, name);
+            assert_eq!(subtype.primary_key, strings(&["pid"]));
+            assert_eq!(&columns(subtype)[2 ..], ["name", "club_cid"]);
+            assert_eq!(keys(subtype), vec![(strings(&["club_cid"]), "club", strings(&["cid"]))]);
+        });
+
+        // keys pointing at the supertype have nothing to point at
+        assert!(table(&schema, "car").foreign_keys.is_empty());
+        assert!(schema.report().contains("foreign key from car to person dropped"));
+    }
+
+    #[test]
+    fn dialects_quote_and_order_differently() {
+        let text = "entity A { attribute aid id } entity B { attribute bid id } relationship R 1:1 link B -- R link R -- A link A -- R2 link R2 -- B relationship R2 1:N";
+        let schema = map(text, Inheritance::ClassTable);
+        assert!(schema.to_sql(Dialect::Postgres).contains("CREATE TABLE \"a\""));
+        assert!(schema.to_sql(Dialect::MySql).contains("CREATE TABLE `a`") && schema.to_sql(Dialect::MySql).contains("ENGINE=InnoDB"));
+
+        // keys that form a cycle are added afterwards, except on sqlite which does not check them
+        assert!(schema.to_sql(Dialect::Postgres).contains("ALTER TABLE"));
+        assert!(!schema.to_sql(Dialect::Sqlite).contains("ALTER TABLE"));
+    }
+
+    #[test]
+    fn tables_with_the_same_name_are_an_error() {
+        let err = map_objects(&dsl::parse("entity PERSON entity person").unwrap(), Inheritance::ClassTable).unwrap_err();
+        assert!(err.contains("PERSON and person would both become table person"), "{err}");
+    }
+}
@@ -1,104 +1,211 @@
-use egui::{pos2, Event, Pos2, Rect, ViewportCommand, Visuals};
-use native_dialog::FileDialog;
+use std::{collections::HashMap, path::Path};
 
-use crate::{draw_lines::draw_link, draw_object::draw_object, objects::Objects, AppState};
+use egui::{epaint::{ClippedPrimitive, ImageData, Primitive, TextureId, Vertex}, pos2, vec2, Color32, ColorImage, FullOutput, Pos2, RawInput, Rect, Shape, TexturesDelta, ViewportId, ViewportInfo, Visuals};
 
-pub struct ScreenshotApp {
-    objects: Objects,
-    size: Rect
+use crate::{draw_lines::draw_link, draw_object::draw_object, draw_tree::draw_tree, objects::Objects, AppState};
+
+// empty space left around the diagram in points
+pub const MARGIN: f32 = 20.0;
+
+// a diagram drawn without a window, fitted to the diagrams bounds
+pub struct HeadlessFrame {
+    pub ctx: egui::Context,
+    pub clip: Rect,
+    pub output: FullOutput,
+    pub textures: HashMap<TextureId, ColorImage>
 }
 
-impl eframe::App for ScreenshotApp {
-    fn update(&mut self, ctx: &egui::Context, _: &mut eframe::Frame) {
-        // create state
-        let mut state = &mut AppState { 
-            clip: Rect { min: Pos2::default(), max: Pos2 { x: self.size.width(), y: self.size.height() } }, 
-            scroll_offset: Pos2::default(),
-            click: false,
-            delete: false,
-            mouse_position: pos2(-100.0, -100.0),
-            selected: None,
-            dragging: false,
-            skip_click_check: false
-        };
+impl HeadlessFrame {
+    // gets every drawn shape with nested shape lists flattened out
+    pub fn flat_shapes(&self) -> Vec<&Shape> {
+        let mut shapes = Vec::new();
+        self.output.shapes.iter().for_each(|clipped| flatten(&clipped.shape, &mut shapes));
+        shapes
+    }
+}
+
+fn flatten<'a>(shape: &'a Shape, out: &mut Vec<&'a Shape>) {
+    match shape {
+        Shape::Vec(shapes) => shapes.iter().for_each(|shape| flatten(shape, out)),
+        Shape::Noop => {},
+        _ => out.push(shape)
+    }
+}
+
+// draws the given objects without opening a window, scale is pixels per point
+pub fn draw_headless(objects: &Objects, scale: f32) -> HeadlessFrame {
+    let mut objects = objects.clone();
+    let ctx = egui::Context::default();
+    ctx.set_visuals(Visuals::light());
+    let mut textures = HashMap::new();
+
+    // do a first pass so object sizes are calculated from their text
+    let output = run_frame(&ctx, &mut objects, Rect::from_min_size(Pos2::ZERO, vec2(1.0, 1.0)), Pos2::ZERO, scale);
+    apply_textures(&mut textures, output.textures_delta);
+
+    // fit the canvas to the diagram
+    let bounds = objects.bounds().unwrap_or(Rect::ZERO).expand(MARGIN);
+    let clip = Rect::from_min_size(Pos2::ZERO, bounds.size());
+    let scroll_offset = pos2(-bounds.center().x, -bounds.center().y);
+
+    // draw for real
+    let mut output = run_frame(&ctx, &mut objects, clip, scroll_offset, scale);
+    apply_textures(&mut textures, std::mem::take(&mut output.textures_delta));
+
+    HeadlessFrame { ctx, clip, output, textures }
+}
+
+// renders the given objects to a png without opening a window, scale is pixels per point
+pub fn screenshot(objects: &Objects, path: &Path, scale: f32) -> Result<(), String> {
+    if scale <= 0.0 || !scale.is_finite() { return Err(format!("Invalid scale {scale}")) }
+
+    let frame = draw_headless(objects, scale);
+    let pixels_per_point = frame.output.pixels_per_point;
+    let primitives = frame.ctx.tessellate(frame.output.shapes, pixels_per_point);
+
+    // rasterize and save
+    let width = (frame.clip.width() * scale).ceil() as u32;
+    let height = (frame.clip.height() * scale).ceil() as u32;
+    let pixels = rasterize(&primitives, &frame.textures, width, height, pixels_per_point);
+    image::save_buffer(path, &pixels, width, height, image::ColorType::Rgba8)
+        .map_err(|err| format!("Could not write {}: {err}", path.display()))
+}
+
+// runs a single egui frame that draws the given objects onto a canvas of the given size
+fn run_frame(ctx: &egui::Context, objects: &mut Objects, clip: Rect, scroll_offset: Pos2, scale: f32) -> FullOutput {
+    let mut input = RawInput { screen_rect: Some(clip), ..Default::default() };
+    input.viewports.insert(ViewportId::ROOT, ViewportInfo { native_pixels_per_point: Some(scale), ..Default::default() });
+
+    ctx.run(input, |ctx| {
+        egui::CentralPanel::default().frame(egui::Frame::none()).show(ctx, |ui| {
+            let mut state = AppState {
+                clip,
+                scroll_offset,
+                mouse_position: pos2(f32::INFINITY, f32::INFINITY),
+                selected: vec![],
+                hovered: None,
+                highlighted: vec![],
+                tree_marks: None
+            };
 
-        // draw
-        egui::CentralPanel::default().show(ctx, |ui| {
             let mut shapes = vec![];
             let mut card_ids = Vec::new();
             let mut union_ids = Vec::new();
-            self.objects.objects.iter_mut().for_each(|object| {
-                shapes.extend(draw_object(object, ui, state));
+            objects.objects.iter_mut().for_each(|object| {
+                shapes.extend(draw_object(object, ui, &mut state));
             });
-            self.objects.links.iter().for_each(|link| {
-                let a = self.objects.objects.iter().find(|a| a.id == link.a).unwrap();
-                let b = self.objects.objects.iter().find(|a| a.id == link.b).unwrap();
+            objects.links.iter().for_each(|link| {
+                // links to missing objects are reported by the validator instead
+                let a = objects.objects.iter().find(|a| a.id == link.a);
+                let b = objects.objects.iter().find(|a| a.id == link.b);
+                let (Some(a), Some(b)) = (a, b) else { return };
                 shapes.extend(draw_link(&mut card_ids, &mut union_ids, a, b, ui, &mut state, &link.minmax));
             });
+            objects.trees.iter_mut().for_each(|tree| shapes.extend(draw_tree(tree, ui, &mut state)));
             ui.painter().extend(shapes);
         });
-        
-        ctx.input(|input| {
-            input.raw.events.iter().for_each(|event| {
-                println!("Event {:?}", event);
-                match event {
-                    Event::Screenshot { image, .. } => {
-                        println!("Screenshot {:?}", image);
-                        let path = FileDialog::new()
-                            .set_location("~")
-                            .add_filter("PNG", &["png"])
-                            .show_save_single_file()
-                            .unwrap();
-                        println!("Path {:?}", path);
-                        if path.is_some() {
-                            image::save_buffer(
-                                &path.unwrap(), 
-                                image.as_raw(), 
-                                image.width() as u32,
-                                image.height() as u32, 
-                                image::ColorType::Rgba8
-                            ).unwrap();
-                        } else {
-                            println!("Could not save!");
-                        }
-                        panic!("Forcing exit!");
-                    },
-                    _ => {}
+    })
+}
+
+// keeps a copy of every texture egui asks us to upload, the font atlas being the important one
+fn apply_textures(textures: &mut HashMap<TextureId, ColorImage>, delta: TexturesDelta) {
+    delta.set.into_iter().for_each(|(id, delta)| {
+        let image = match delta.image {
+            ImageData::Color(image) => image.as_ref().clone(),
+            ImageData::Font(font) => ColorImage { size: font.size, pixels: font.srgba_pixels(None).collect() }
+        };
+
+        match delta.pos {
+            // patch part of an existing texture
+            Some([x, y]) => {
+                let Some(texture) = textures.get_mut(&id) else { return };
+                let texture_width = texture.width();
+                for row in 0 .. image.height() {
+                    for column in 0 .. image.width() {
+                        texture.pixels[(y + row) * texture_width + x + column] = image.pixels[row * image.width() + column];
+                    }
                 }
-            });
+            },
+
+            // replace the whole texture
+            None => { textures.insert(id, image); }
+        }
+    });
+    delta.free.iter().for_each(|id| { textures.remove(id); });
+}
+
+// draws tessellated triangles into a rgba buffer on a white background
+fn rasterize(primitives: &[ClippedPrimitive], textures: &HashMap<TextureId, ColorImage>, width: u32, height: u32, scale: f32) -> Vec<u8> {
+    // premultiplied colors in 0 - 1
+    let mut buffer = vec![[1.0f32; 4]; (width * height) as usize];
+
+    primitives.iter().for_each(|primitive| {
+        let Primitive::Mesh(mesh) = &primitive.primitive else { return };
+        let texture = textures.get(&mesh.texture_id);
+        let clip = Rect { min: primitive.clip_rect.min * scale, max: primitive.clip_rect.max * scale }
+            .intersect(Rect { min: Pos2::ZERO, max: pos2(width as f32, height as f32) });
+
+        mesh.indices.chunks_exact(3).for_each(|triangle| {
+            let vertices = [
+                &mesh.vertices[triangle[0] as usize],
+                &mesh.vertices[triangle[1] as usize],
+                &mesh.vertices[triangle[2] as usize]
+            ];
+            draw_triangle(&mut buffer, width, clip, vertices, texture, scale);
         });
+    });
+
+    buffer.iter()
+        .flat_map(|pixel| pixel.map(|channel| (channel.clamp(0.0, 1.0) * 255.0).round() as u8))
+        .collect()
+}
+
+fn draw_triangle(buffer: &mut [[f32; 4]], width: u32, clip: Rect, vertices: [&Vertex; 3], texture: Option<&ColorImage>, scale: f32) {
+    let points = vertices.map(|vertex| vertex.pos * scale);
+    let area = edge(points[0], points[1], points[2]);
+    if area == 0.0 || clip.is_negative() { return }
+
+    // only visit pixels the triangle can touch
+    let min_x = points.iter().map(|p| p.x).fold(f32::MAX, f32::min).max(clip.min.x).floor() as u32;
+    let min_y = points.iter().map(|p| p.y).fold(f32::MAX, f32::min).max(clip.min.y).floor() as u32;
+    let max_x = points.iter().map(|p| p.x).fold(f32::MIN, f32::max).min(clip.max.x).ceil() as u32;
+    let max_y = points.iter().map(|p| p.y).fold(f32::MIN, f32::max).min(clip.max.y).ceil() as u32;
+
+    for y in min_y .. max_y {
+        for x in min_x .. max_x {
+            // get barycentric weights of the pixel center
+            let p = pos2(x as f32 + 0.5, y as f32 + 0.5);
+            let weights = [
+                edge(points[1], points[2], p) / area,
+                edge(points[2], points[0], p) / area,
+                edge(points[0], points[1], p) / area
+            ];
+            if weights.iter().any(|w| *w < 0.0) { continue }
+
+            // interpolate color and uv
+            let mut color = [0.0; 4];
+            let mut uv = Pos2::ZERO;
+            for (vertex, weight) in vertices.iter().zip(weights) {
+                color.iter_mut().zip(vertex.color.to_array()).for_each(|(c, v)| *c += v as f32 / 255.0 * weight);
+                uv += vertex.uv.to_vec2() * weight;
+            }
+            let sample = texture.map(|texture| sample(texture, uv)).unwrap_or(Color32::WHITE).to_array();
+            color.iter_mut().zip(sample).for_each(|(c, s)| *c *= s as f32 / 255.0);
+
+            // blend over what is already there
+            let pixel = &mut buffer[(y * width + x) as usize];
+            let alpha = color[3];
+            pixel.iter_mut().zip(color).for_each(|(d, s)| *d = s + *d * (1.0 - alpha));
+        }
     }
 }
 
-pub fn screenshot(objects: Objects) {
-    // get size of objects
-    // let mut size = Rect { min: Pos2 { x: f32::MAX, y: f32::MAX }, max: Pos2 { x: f32::MIN, y: f32::MIN } };
-    // objects.objects.iter().for_each(|object| {
-    //     let min = pos2(object.x - object.width, object.y - object.width);
-    //     let max = pos2(object.x + object.width, object.y + object.width);
-    //     if min.x < size.min.x { size.min.x = min.x; }
-    //     if min.y < size.min.y { size.min.y = min.y; }
-    //     if max.x > size.max.x { size.max.x = max.x; }
-    //     if max.y > size.max.y { size.max.y = max.y; }
-    // });
-
-    let width = 1920.0;
-    let height = 1080.0;
-    println!("Size {} {}", width, height);
-
-    // run screen shot app
-    let native_options = eframe::NativeOptions {
-        viewport: egui::ViewportBuilder::default()
-            .with_inner_size([width, height])
-            .with_min_inner_size([width, height]),
-        ..Default::default()
-    };
-
-    // run screenshot app
-    let app = ScreenshotApp { objects, size: Rect { min: pos2(0.0, 0.0), max: pos2(1920.0, 1080.0) } };
-    eframe::run_native("screenshot", native_options, Box::new(|ctx| {
-        ctx.egui_ctx.set_visuals(Visuals::light());
-        ctx.egui_ctx.send_viewport_cmd(ViewportCommand::Screenshot);
-        Box::new(app)
-    })).unwrap();
-}
\ No newline at end of file
+fn edge(a: Pos2, b: Pos2, p: Pos2) -> f32 {
+    (b.x - a.x) * (p.y - a.y) - (b.y - a.y) * (p.x - a.x)
+}
+
+fn sample(texture: &ColorImage, uv: Pos2) -> Color32 {
+    let x = ((uv.x * texture.width() as f32) as usize).min(texture.width() - 1);
+    let y = ((uv.y * texture.height() as f32) as usize).min(texture.height() - 1);
+    texture.pixels[y * texture.width() + x]
+}
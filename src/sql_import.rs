@@ -0,0 +1,433 @@
+use std::{collections::HashMap, f32::consts::PI};
+
+use crate::objects::{CardType, ObjectType, Objects};
+
+// distance between tables when placing them in a grid
+const GRID_SPACING_X: f32 = 360.0;
+const GRID_SPACING_Y: f32 = 280.0;
+const ATTRIBUTE_RADIUS: f32 = 100.0;
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    // bare words keep their case, quoted is true for "a", `a` and [a]
+    Word { text: String, quoted: bool },
+    Symbol(char),
+    Literal
+}
+
+impl Token {
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self, Token::Word { text, quoted: false } if text.eq_ignore_ascii_case(keyword))
+    }
+
+    fn word(&self) -> Option<&str> {
+        match self {
+            Token::Word { text, .. } => Some(text),
+            _ => None
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+struct ParsedTable {
+    name: String,
+    columns: Vec<String>,
+    primary_key: Vec<String>,
+    unique: Vec<Vec<String>>,
+    foreign_keys: Vec<ParsedForeignKey>
+}
+
+#[derive(Clone, Debug, Default)]
+struct ParsedForeignKey {
+    name: Option<String>,
+    columns: Vec<String>,
+    table: String
+}
+
+// builds a diagram from create table and alter table statements
+pub fn import_sql(sql: &str) -> Result<Objects, String> {
+    let tokens = tokenize(sql)?;
+    let mut tables: Vec<ParsedTable> = Vec::new();
+
+    tokens.split(|token| *token == Token::Symbol(';')).try_for_each(|statement| {
+        match parse_create_table(statement)? {
+            Some(table) => {
+                // a later definition of the same table replaces the earlier one
+                tables.retain(|a| !a.name.eq_ignore_ascii_case(&table.name));
+                tables.push(table);
+                Ok(())
+            },
+            None => parse_alter_table(statement, &mut tables)
+        }
+    })?;
+
+    if tables.is_empty() { return Err("No CREATE TABLE statements found".into()) }
+    Ok(build_objects(&tables))
+}
+
+fn tokenize(sql: &str) -> Result<Vec<Token>, String> {
+    let chars = sql.chars().collect::<Vec<char>>();
+    let mut tokens = Vec::new();
+    let mut idx = 0;
+    let mut line = 1;
+
+    // reads until the closing character, doubled closers are escapes
+    let read_quoted = |idx: &mut usize, line: &mut usize, close: char| -> Result<String, String> {
+        let start_line = *line;
+        let mut text = String::new();
+        *idx += 1;
+        loop {
+            match chars.get(*idx) {
+                None => return Err(format!("Unterminated quote starting on line {start_line}")),
+                Some(c) if *c == close => {
+                    if chars.get(*idx + 1) == Some(&close) { text.push(close); *idx += 2; }
+                    else { *idx += 1; return Ok(text) }
+                },
+                Some(c) => {
+                    if *c == '\n' { *line += 1; }
+                    text.push(*c);
+                    *idx += 1;
+                }
+            }
+        }
+    };
+
+    while idx < chars.len() {
+        let c = chars[idx];
+        match c {
+            '\n' => { line += 1; idx += 1; },
+            c if c.is_whitespace() => idx += 1,
+
+            // comments
+            '-' if chars.get(idx + 1) == Some(&'-') => {
+                while idx < chars.len() && chars[idx] != '\n' { idx += 1; }
+            },
+            '#' => {
+                while idx < chars.len() && chars[idx] != '\n' { idx += 1; }
+            },
+            '/' if chars.get(idx + 1) == Some(&'*') => {
+                let start_line = line;
+                idx += 2;
+                while idx < chars.len() && !(chars[idx] == '*' && chars.get(idx + 1) == Some(&'/')) {
+                    if chars[idx] == '\n' { line += 1; }
+                    idx += 1;
+                }
+                if idx >= chars.len() { return Err(format!("Unterminated comment starting on line {start_line}")) }
+                idx += 2;
+            },
+
+            // quoted identifiers and strings
+            '"' | '`' | '[' => {
+                let close = match c { '[' => ']', other => other };
+                let text = read_quoted(&mut idx, &mut line, close)?;
+                tokens.push(Token::Word { text, quoted: true });
+            },
+            '\'' => {
+                read_quoted(&mut idx, &mut line, '\'')?;
+                tokens.push(Token::Literal);
+            },
+
+            c if c.is_alphanumeric() || c == '_' => {
+                let start = idx;
+                while idx < chars.len() && (chars[idx].is_alphanumeric() || chars[idx] == '_' || chars[idx] == '$') { idx += 1; }
+                tokens.push(Token::Word { text: chars[start .. idx].iter().collect(), quoted: false });
+            },
+
+            c => { tokens.push(Token::Symbol(c)); idx += 1; }
+        }
+    }
+
+    Ok(tokens)
+}
+
+// gets a possibly schema qualified name, returning the last part and how many tokens it used
+fn qualified_name(tokens: &[Token]) -> Option<(String, usize)> {
+    let mut name = tokens.first()?.word()?.to_string();
+    let mut used = 1;
+    while tokens.get(used) == Some(&Token::Symbol('.')) {
+        match tokens.get(used + 1).and_then(|token| token.word()) {
+            Some(part) => { name = part.to_string(); used += 2; },
+            None => break
+        }
+    }
+    Some((name, used))
+}
+
+// reads a parenthesized list of column names, returning them and how many tokens it used
+fn column_list(tokens: &[Token]) -> Option<(Vec<String>, usize)> {
+    if tokens.first() != Some(&Token::Symbol('(')) { return None }
+    let end = tokens.iter().position(|token| *token == Token::Symbol(')'))?;
+    let columns = tokens[1 .. end].iter()
+        .filter_map(|token| token.word().map(|word| word.to_string()))
+        .collect::<Vec<String>>();
+    Some((columns, end + 1))
+}
+
+// splits the contents of a parenthesized group on top level commas
+fn split_group(tokens: &[Token]) -> Vec<&[Token]> {
+    let mut parts = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    tokens.iter().enumerate().for_each(|(idx, token)| match token {
+        Token::Symbol('(') => depth += 1,
+        Token::Symbol(')') => depth -= 1,
+        Token::Symbol(',') if depth == 0 => { parts.push(&tokens[start .. idx]); start = idx + 1; },
+        _ => {}
+    });
+    parts.push(&tokens[start ..]);
+    parts.into_iter().filter(|part| !part.is_empty()).collect()
+}
+
+// finds the index of a keyword followed by another, like primary key
+fn find_pair(tokens: &[Token], first: &str, second: &str) -> Option<usize> {
+    tokens.windows(2).position(|pair| pair[0].is_keyword(first) && pair[1].is_keyword(second))
+}
+
+// none if the statement is not a create table with a column list, an error if it is one but cut short
+fn parse_create_table(statement: &[Token]) -> Result<Option<ParsedTable>, String> {
+    if !statement.first().is_some_and(|token| token.is_keyword("create")) { return Ok(None) }
+    let Some(table_idx) = statement.iter().take(4).position(|token| token.is_keyword("table")) else { return Ok(None) };
+    let mut idx = table_idx + 1;
+    if statement.get(idx).is_some_and(|token| token.is_keyword("if")) { idx += 3; }
+    let (name, used) = statement.get(idx ..).and_then(qualified_name).ok_or("CREATE TABLE without a table name")?;
+    idx += used;
+
+    // find the matching close of the definition list, create table as select has none
+    match statement.get(idx) {
+        Some(Token::Symbol('(')) => {},
+        Some(_) => return Ok(None),
+        None => return Err(format!("Table {name} has no column list"))
+    }
+    let mut depth = 0;
+    let end = statement[idx ..].iter().position(|token| {
+        match token {
+            Token::Symbol('(') => depth += 1,
+            Token::Symbol(')') => depth -= 1,
+            _ => {}
+        }
+        depth == 0
+    }).ok_or_else(|| format!("Table {name} has an unclosed parenthesis"))? + idx;
+
+    let mut table = ParsedTable { name, ..Default::default() };
+    split_group(&statement[idx + 1 .. end]).into_iter().try_for_each(|definition| parse_definition(definition, &mut table))?;
+    Ok(Some(table))
+}
+
+fn parse_definition(definition: &[Token], table: &mut ParsedTable) -> Result<(), String> {
+    let incomplete = |what: &str| format!("Table {} has an incomplete {what}", table.name);
+
+    // named constraints
+    let (constraint_name, definition) = if definition[0].is_keyword("constraint") && definition.len() > 2 {
+        (definition[1].word().map(|a| a.to_string()), &definition[2 ..])
+    } else { (None, definition) };
+
+    if definition[0].is_keyword("primary") {
+        let (columns, _) = definition.get(2 ..).and_then(column_list).ok_or_else(|| incomplete("primary key"))?;
+        table.primary_key = columns;
+    } else if definition[0].is_keyword("foreign") {
+        let key = parse_foreign_key(definition, constraint_name).ok_or_else(|| incomplete("foreign key"))?;
+        table.foreign_keys.push(key);
+    } else if definition[0].is_keyword("unique") {
+        let start = definition.iter().position(|token| *token == Token::Symbol('(')).ok_or_else(|| incomplete("unique constraint"))?;
+        let (columns, _) = column_list(&definition[start ..]).ok_or_else(|| incomplete("unique constraint"))?;
+        table.unique.push(columns);
+    } else if ["key", "index", "check", "exclude", "fulltext", "spatial"].iter().any(|keyword| definition[0].is_keyword(keyword)) {
+        // indexes and checks do not change the diagram
+    } else if let Some(column) = definition[0].word() {
+        let column = column.to_string();
+
+        // inline constraints
+        if find_pair(definition, "primary", "key").is_some() { table.primary_key = vec![column.clone()]; }
+        if definition[1 ..].iter().any(|token| token.is_keyword("unique")) { table.unique.push(vec![column.clone()]); }
+        if let Some(references) = definition.iter().position(|token| token.is_keyword("references")) {
+            if let Some((target, _)) = qualified_name(&definition[references + 1 ..]) {
+                table.foreign_keys.push(ParsedForeignKey { name: constraint_name, columns: vec![column.clone()], table: target });
+            }
+        }
+        table.columns.push(column);
+    }
+    Ok(())
+}
+
+// parses foreign key (columns) references table (columns)
+fn parse_foreign_key(definition: &[Token], name: Option<String>) -> Option<ParsedForeignKey> {
+    let start = definition.iter().position(|token| *token == Token::Symbol('('))?;
+    let (columns, _) = column_list(&definition[start ..])?;
+    let references = definition.iter().position(|token| token.is_keyword("references"))?;
+    let (table, _) = qualified_name(&definition[references + 1 ..])?;
+    Some(ParsedForeignKey { name, columns, table })
+}
+
+// alter table statements for tables that were not created are skipped
+fn parse_alter_table(statement: &[Token], tables: &mut [ParsedTable]) -> Result<(), String> {
+    if statement.len() < 4 || !statement[0].is_keyword("alter") || !statement[1].is_keyword("table") { return Ok(()) }
+    let mut idx = 2;
+    while statement.get(idx).is_some_and(|token| token.is_keyword("only") || token.is_keyword("if") || token.is_keyword("exists")) { idx += 1; }
+    let Some((name, used)) = statement.get(idx ..).and_then(qualified_name) else { return Ok(()) };
+    let Some(table) = tables.iter_mut().find(|table| table.name.eq_ignore_ascii_case(&name)) else { return Ok(()) };
+
+    // every add clause is handled like a table definition
+    split_group(&statement[idx + used ..]).into_iter().try_for_each(|clause| {
+        if clause.len() > 1 && clause[0].is_keyword("add") {
+            let clause = if clause[1].is_keyword("column") { &clause[2 ..] } else { &clause[1 ..] };
+            if !clause.is_empty() { parse_definition(clause, table)?; }
+        }
+        Ok(())
+    })
+}
+
+fn entity_name(name: &str) -> String { name.to_uppercase().replace(' ', "_") }
+
+fn same_columns(a: &[String], b: &[String]) -> bool {
+    a.len() == b.len() && a.iter().all(|column| b.iter().any(|other| other.eq_ignore_ascii_case(column)))
+}
+
+fn contains_column(columns: &[String], column: &str) -> bool {
+    columns.iter().any(|other| other.eq_ignore_ascii_case(column))
+}
+
+// turns parsed tables into entities, attributes and relationships
+fn build_objects(tables: &[ParsedTable]) -> Objects {
+    let mut objects = Objects::default();
+    let columns = (tables.len() as f32).sqrt().ceil().max(1.0) as usize;
+
+    // pure junction tables have a key made only of two or more foreign keys
+    let is_junction = |table: &ParsedTable| {
+        let key_columns = table.foreign_keys.iter().flat_map(|key| key.columns.clone()).collect::<Vec<String>>();
+        table.foreign_keys.len() >= 2 && (
+            same_columns(&table.primary_key, &key_columns) ||
+            (table.primary_key.is_empty() && table.columns.iter().all(|column| contains_column(&key_columns, column)))
+        )
+    };
+
+    // tables that are not junctions become entities in a grid
+    let mut entity_ids = HashMap::new();
+    tables.iter().filter(|table| !is_junction(table)).enumerate().for_each(|(idx, table)| {
+        let x = (idx % columns) as f32 * GRID_SPACING_X;
+        let y = (idx / columns) as f32 * GRID_SPACING_Y;
+
+        // a key that extends a foreign key makes this a weak entity
+        let weak = table.foreign_keys.iter().any(|key| {
+            key.columns.iter().all(|column| contains_column(&table.primary_key, column)) && table.primary_key.len() > key.columns.len()
+        });
+        let entity = objects.add(if weak { ObjectType::EntityDependent } else { ObjectType::Entity }, x, y);
+        entity.name = entity_name(&table.name);
+        let entity_id = entity.id;
+        entity_ids.insert(table.name.to_lowercase(), entity_id);
+
+        // columns that only exist to reference another table are shown as relationships instead
+        let attributes = table.columns.iter()
+            .filter(|column| !table.foreign_keys.iter().any(|key| contains_column(&key.columns, column)))
+            .collect::<Vec<&String>>();
+        attributes.iter().enumerate().for_each(|(idx, column)| {
+            let angle = idx as f32 / attributes.len() as f32 * 2.0 * PI - PI / 2.0;
+            let attribute = objects.add(
+                ObjectType::Parameter { is_id: contains_column(&table.primary_key, column) },
+                x + angle.cos() * ATTRIBUTE_RADIUS,
+                y + angle.sin() * ATTRIBUTE_RADIUS * 0.7
+            );
+            attribute.name = column.to_string();
+            let attribute_id = attribute.id;
+            objects.link(entity_id, attribute_id);
+        });
+    });
+
+    let position = |objects: &Objects, id: u32| objects.objects.iter().find(|a| a.id == id).map(|a| (a.x, a.y)).unwrap_or_default();
+
+    // moves a position down until it is clear of other objects
+    let free_position = |objects: &Objects, x: f32, mut y: f32| {
+        while objects.objects.iter().any(|a| (a.x - x).abs() < 120.0 && (a.y - y).abs() < 80.0) { y += 40.0; }
+        (x, y)
+    };
+
+    // foreign keys become relationships between the two entities
+    tables.iter().filter(|table| !is_junction(table)).for_each(|table| {
+        let Some(child) = entity_ids.get(&table.name.to_lowercase()).copied() else { return };
+        table.foreign_keys.iter().for_each(|key| {
+            let Some(parent) = entity_ids.get(&key.table.to_lowercase()).copied() else { return };
+
+            let identifying = key.columns.iter().all(|column| contains_column(&table.primary_key, column)) && table.primary_key.len() > key.columns.len();
+            let one_to_one = same_columns(&key.columns, &table.primary_key) || table.unique.iter().any(|unique| same_columns(unique, &key.columns));
+            let card = if one_to_one { CardType::OneToOne } else { CardType::OneToMany };
+
+            // place the diamond between the two tables
+            let (px, py) = position(&objects, parent);
+            let (cx, cy) = position(&objects, child);
+            let (x, y) = free_position(&objects, (px + cx) / 2.0, (py + cy) / 2.0 + if parent == child { GRID_SPACING_Y / 2.0 } else { 0.0 });
+            let relationship = objects.add(if identifying { ObjectType::RelationshipDependent { card } } else { ObjectType::Relationship { card } }, x, y);
+            relationship.name = entity_name(&key.name.clone().unwrap_or_else(|| format!("{}_{}", table.name, key.table)));
+            let relationship_id = relationship.id;
+
+            // the referenced side is linked first so it gets the one
+            objects.link(parent, relationship_id);
+            objects.link(relationship_id, child);
+        });
+    });
+
+    // junction tables collapse into many to many relationships
+    tables.iter().filter(|table| is_junction(table)).for_each(|table| {
+        let members = table.foreign_keys.iter()
+            .filter_map(|key| entity_ids.get(&key.table.to_lowercase()).copied())
+            .collect::<Vec<u32>>();
+        if members.is_empty() { return }
+
+        let count = members.len() as f32;
+        let (x, y) = members.iter().map(|id| position(&objects, *id)).fold((0.0, 0.0), |a, b| (a.0 + b.0 / count, a.1 + b.1 / count));
+        let (x, y) = free_position(&objects, x, y + GRID_SPACING_Y / 4.0);
+        let relationship = objects.add(ObjectType::Relationship { card: CardType::ManyToMany }, x, y);
+        relationship.name = entity_name(&table.name);
+        let relationship_id = relationship.id;
+        members.iter().for_each(|id| objects.link(*id, relationship_id));
+
+        // anything else in the junction is an attribute of the relationship
+        let attributes = table.columns.iter()
+            .filter(|column| !table.foreign_keys.iter().any(|key| contains_column(&key.columns, column)))
+            .collect::<Vec<&String>>();
+        attributes.iter().enumerate().for_each(|(idx, column)| {
+            let (x, y) = free_position(&objects, x + (idx as f32 - (attributes.len() as f32 - 1.0) / 2.0) * 90.0, y + 80.0);
+            let attribute = objects.add(ObjectType::Parameter { is_id: false }, x, y);
+            attribute.name = column.to_string();
+            let attribute_id = attribute.id;
+            objects.link(relationship_id, attribute_id);
+        });
+    });
+
+    objects
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn imports_tables_and_foreign_keys() {
+        let sql = "CREATE TABLE person (id INT PRIMARY KEY, name TEXT);
+            CREATE TABLE IF NOT EXISTS pet (id INT, owner INT, PRIMARY KEY (id), FOREIGN KEY (owner) REFERENCES person (id));";
+        let objects = import_sql(sql).unwrap();
+        let names = objects.objects.iter().map(|a| a.name.as_str()).collect::<Vec<&str>>();
+        assert!(names.contains(&"PERSON") && names.contains(&"PET"));
+        assert!(objects.objects.iter().any(|a| matches!(a.object_type, ObjectType::Relationship { card: CardType::OneToMany })));
+    }
+
+    #[test]
+    fn malformed_ddl_is_an_error() {
+        [
+            "CREATE TABLE t (PRIMARY);",
+            "CREATE TABLE t (id INT); ALTER TABLE t ADD PRIMARY;",
+            "CREATE TABLE IF",
+            "CREATE TABLE",
+            "CREATE TABLE t",
+            "CREATE TABLE t (id INT",
+            "CREATE TABLE t (id INT, CONSTRAINT c PRIMARY);",
+            "CREATE TABLE t (id INT, FOREIGN KEY);",
+            "CREATE TABLE t (id INT, UNIQUE);",
+            "CREATE TABLE t (name TEXT DEFAULT 'unterminated);"
+        ].iter().for_each(|sql| assert!(import_sql(sql).is_err(), "{sql} should not import"));
+    }
+
+    #[test]
+    fn other_statements_are_skipped() {
+        let objects = import_sql("CREATE INDEX i ON t (a); CREATE TABLE t (a INT); ALTER TABLE missing ADD COLUMN b INT;").unwrap();
+        assert_eq!(objects.objects.iter().filter(|a| a.object_type == ObjectType::Entity).count(), 1);
+    }
+}
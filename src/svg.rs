@@ -0,0 +1,132 @@
+use std::{fmt::Write, path::Path};
+
+use egui::{epaint::{CircleShape, PathShape, QuadraticBezierShape, RectShape, TextShape}, Color32, Pos2, Shape, Stroke};
+
+use crate::{objects::Objects, screenshot::draw_headless};
+
+// writes the given objects to an svg file
+pub fn export_svg(objects: &Objects, path: &Path) -> Result<(), String> {
+    std::fs::write(path, to_svg(objects))
+        .map_err(|err| format!("Could not write {}: {err}", path.display()))
+}
+
+// converts the given objects into an svg document, using the same shapes the canvas draws
+pub fn to_svg(objects: &Objects) -> String {
+    let frame = draw_headless(objects, 1.0);
+    let width = frame.clip.width();
+    let height = frame.clip.height();
+
+    let mut svg = String::new();
+    let _ = writeln!(svg, r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+    let _ = writeln!(svg, r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}">"#);
+    let _ = writeln!(svg, r##"<rect x="0" y="0" width="{width}" height="{height}" fill="#ffffff"/>"##);
+
+    // join line segments that continue each other, circles are drawn as many short segments
+    let mut line: Vec<Pos2> = Vec::new();
+    let mut line_stroke = Stroke::NONE;
+    frame.flat_shapes().into_iter().for_each(|shape| {
+        if let Shape::LineSegment { points, stroke } = shape {
+            if line.last() == Some(&points[0]) && line_stroke == *stroke {
+                line.push(points[1]);
+                return
+            }
+            write_line(&mut svg, &line, &line_stroke);
+            line = points.to_vec();
+            line_stroke = *stroke;
+        } else {
+            write_line(&mut svg, &line, &line_stroke);
+            line.clear();
+            write_shape(&mut svg, shape);
+        }
+    });
+    write_line(&mut svg, &line, &line_stroke);
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+fn write_line(svg: &mut String, points: &[Pos2], stroke: &Stroke) {
+    let _ = match points {
+        [] | [_] => Ok(()),
+        [a, b] => writeln!(svg, r#"<line x1="{}" y1="{}" x2="{}" y2="{}" {}/>"#, a.x, a.y, b.x, b.y, stroke_attributes(stroke)),
+        _ => writeln!(svg, r#"<polyline points="{}" fill="none" {}/>"#, points_attribute(points), stroke_attributes(stroke))
+    };
+}
+
+fn write_shape(svg: &mut String, shape: &Shape) {
+    // skip invisible shapes like the canvas background
+    if shape.visual_bounding_rect().is_negative() { return }
+
+    let _ = match shape {
+        Shape::Rect(RectShape { rect, fill, stroke, .. }) => writeln!(
+            svg, r#"<rect x="{}" y="{}" width="{}" height="{}" {} {}/>"#,
+            rect.min.x, rect.min.y, rect.width(), rect.height(), fill_attributes(*fill), stroke_attributes(stroke)
+        ),
+
+        Shape::Circle(CircleShape { center, radius, fill, stroke }) => writeln!(
+            svg, r#"<circle cx="{}" cy="{}" r="{}" {} {}/>"#,
+            center.x, center.y, radius, fill_attributes(*fill), stroke_attributes(stroke)
+        ),
+
+        Shape::Path(PathShape { points, closed, fill, stroke }) => writeln!(
+            svg, r#"<{} points="{}" {} {}/>"#,
+            if *closed { "polygon" } else { "polyline" }, points_attribute(points),
+            fill_attributes(if *closed { *fill } else { Color32::TRANSPARENT }), stroke_attributes(stroke)
+        ),
+
+        Shape::QuadraticBezier(QuadraticBezierShape { points, closed, fill, stroke }) => writeln!(
+            svg, r#"<path d="M {} {} Q {} {} {} {}{}" {} {}/>"#,
+            points[0].x, points[0].y, points[1].x, points[1].y, points[2].x, points[2].y,
+            if *closed { " Z" } else { "" }, fill_attributes(*fill), stroke_attributes(stroke)
+        ),
+
+        Shape::Text(text) => write_text(svg, text),
+
+        _ => Ok(())
+    };
+}
+
+// text is kept as real text so it can be searched and selected
+fn write_text(svg: &mut String, text: &TextShape) -> std::fmt::Result {
+    let galley = &text.galley;
+    let Some(format) = galley.job.sections.first().map(|section| &section.format) else { return Ok(()) };
+    let color = text.override_text_color.unwrap_or(format.color);
+    let center = text.pos + galley.rect.center().to_vec2();
+
+    writeln!(
+        svg, r#"<text x="{}" y="{}" font-family="monospace" font-size="{}" text-anchor="middle" dominant-baseline="central" {}>{}</text>"#,
+        center.x, center.y, format.font_id.size, fill_attributes(color), escape(&galley.job.text)
+    )
+}
+
+fn points_attribute(points: &[Pos2]) -> String {
+    points.iter().map(|point| format!("{},{}", point.x, point.y)).collect::<Vec<String>>().join(" ")
+}
+
+fn fill_attributes(color: Color32) -> String {
+    match color_attributes(color) {
+        Some((color, opacity)) => format!(r#"fill="{color}"{}"#, opacity.map(|o| format!(r#" fill-opacity="{o}""#)).unwrap_or_default()),
+        None => r#"fill="none""#.into()
+    }
+}
+
+fn stroke_attributes(stroke: &Stroke) -> String {
+    match color_attributes(stroke.color) {
+        Some((color, opacity)) if stroke.width > 0.0 => format!(
+            r#"stroke="{color}" stroke-width="{}"{}"#,
+            stroke.width, opacity.map(|o| format!(r#" stroke-opacity="{o}""#)).unwrap_or_default()
+        ),
+        _ => r#"stroke="none""#.into()
+    }
+}
+
+// gets a hex color and an opacity if it is not opaque, none if the color is invisible
+fn color_attributes(color: Color32) -> Option<(String, Option<f32>)> {
+    let [r, g, b, a] = color.to_srgba_unmultiplied();
+    if a == 0 { return None }
+    Some((format!("#{r:02x}{g:02x}{b:02x}"), if a < 255 { Some(a as f32 / 255.0) } else { None }))
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
@@ -0,0 +1,370 @@
+use std::collections::HashMap;
+
+use crate::objects::{Object, ObjectType, Objects, Polymorph};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Error,
+    Warning
+}
+
+// a problem with the diagram and the objects involved in it
+#[derive(Clone, Debug)]
+pub struct Finding {
+    pub severity: Severity,
+    pub message: String,
+    pub objects: Vec<u32>
+}
+
+impl std::fmt::Display for Finding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let severity = match self.severity { Severity::Error => "error", Severity::Warning => "warning" };
+        write!(f, "{severity}: {}", self.message)
+    }
+}
+
+// checks the diagram against the rules of chen notation, errors first
+pub fn validate(objects: &Objects) -> Vec<Finding> {
+    let validator = Validator { objects };
+    let mut findings = Vec::new();
+    findings.extend(validator.missing_links());
+    findings.extend(validator.relationships());
+    findings.extend(validator.attributes());
+    findings.extend(validator.weak_entities());
+    findings.extend(validator.polymorphs());
+    findings.extend(validator.duplicate_names());
+    findings.extend(validator.keys());
+    findings.extend(validator.trees());
+    findings.sort_by_key(|finding| finding.severity);
+    findings
+}
+
+fn is_entity(object: &Object) -> bool { matches!(object.object_type, ObjectType::Entity | ObjectType::EntityDependent) }
+fn is_attribute(object: &Object) -> bool { matches!(object.object_type, ObjectType::Parameter { .. } | ObjectType::FunctionParameter { .. }) }
+
+// shown in messages so objects without a name can still be found
+fn label(object: &Object) -> String {
+    if object.name.is_empty() { format!("#{}", object.id) } else { object.name.clone() }
+}
+
+struct Validator<'a> {
+    objects: &'a Objects
+}
+
+impl<'a> Validator<'a> {
+    fn object(&self, id: u32) -> Option<&'a Object> { self.objects.objects.iter().find(|object| object.id == id) }
+
+    fn neighbors(&self, id: u32) -> Vec<&'a Object> {
+        self.objects.links.iter()
+            .filter_map(|link| {
+                if link.a == id { self.object(link.b) }
+                else if link.b == id { self.object(link.a) }
+                else { None }
+            })
+            .collect()
+    }
+
+    // links to ids that are not in the diagram
+    fn missing_links(&self) -> Vec<Finding> {
+        self.objects.links.iter()
+            .filter_map(|link| {
+                let missing = [link.a, link.b].into_iter().filter(|id| self.object(*id).is_none()).collect::<Vec<u32>>();
+                if missing.is_empty() { return None }
+                let existing = [link.a, link.b].into_iter().filter(|id| self.object(*id).is_some()).collect::<Vec<u32>>();
+                let ids = missing.iter().map(|id| format!("#{id}")).collect::<Vec<String>>().join(" and ");
+                Some(Finding { severity: Severity::Error, message: format!("link {} - {} points at missing object {ids}", link.a, link.b), objects: existing })
+            })
+            .collect()
+    }
+
+    // relationships need at least two entities, the same entity twice counts for recursive ones
+    fn relationships(&self) -> Vec<Finding> {
+        self.objects.objects.iter()
+            .filter(|object| matches!(object.object_type, ObjectType::Relationship { .. } | ObjectType::RelationshipDependent { .. }))
+            .filter_map(|relationship| {
+                let entities = self.neighbors(relationship.id).into_iter().filter(|a| is_entity(a)).count();
+                if entities >= 2 { return None }
+                Some(Finding {
+                    severity: Severity::Error,
+                    message: format!("relationship {} connects {entities} {}, it needs at least two", label(relationship), if entities == 1 { "entity" } else { "entities" }),
+                    objects: vec![relationship.id]
+                })
+            })
+            .collect()
+    }
+
+    // every attribute belongs to exactly one owner, parts of a composite attribute belong to it
+    fn attributes(&self) -> Vec<Finding> {
+        let depths = self.attribute_depths();
+        self.objects.objects.iter()
+            .filter(|object| is_attribute(object))
+            .filter_map(|attribute| {
+                let depth = depths.get(&attribute.id).copied();
+                let owners = self.neighbors(attribute.id).into_iter()
+                    .filter(|owner| {
+                        if !is_attribute(owner) { return true }
+                        matches!((depths.get(&owner.id), depth), (Some(owner), Some(depth)) if *owner < depth)
+                    })
+                    .collect::<Vec<&Object>>();
+                let message = match owners.len() {
+                    0 => format!("attribute {} is not attached to anything", label(attribute)),
+                    1 => return None,
+                    _ => format!(
+                        "attribute {} is attached to {} owners: {}",
+                        label(attribute), owners.len(), owners.iter().map(|a| label(a)).collect::<Vec<String>>().join(", ")
+                    )
+                };
+                let mut ids = vec![attribute.id];
+                ids.extend(owners.iter().map(|a| a.id));
+                Some(Finding { severity: Severity::Error, message, objects: ids })
+            })
+            .collect()
+    }
+
+    // how many links away from a non attribute each attribute is
+    fn attribute_depths(&self) -> HashMap<u32, usize> {
+        let mut depths = HashMap::new();
+        let mut frontier = self.objects.objects.iter().filter(|a| !is_attribute(a)).map(|a| a.id).collect::<Vec<u32>>();
+        let mut depth = 0;
+        while !frontier.is_empty() {
+            depth += 1;
+            frontier = frontier.iter()
+                .flat_map(|id| self.neighbors(*id))
+                .filter(|a| is_attribute(a))
+                .map(|a| a.id)
+                .collect::<Vec<u32>>()
+                .into_iter()
+                .filter(|id| {
+                    if depths.contains_key(id) { return false }
+                    depths.insert(*id, depth);
+                    true
+                })
+                .collect();
+        }
+        depths
+    }
+
+    fn weak_entities(&self) -> Vec<Finding> {
+        self.objects.objects.iter()
+            .filter(|object| object.object_type == ObjectType::EntityDependent)
+            .filter(|weak| !self.neighbors(weak.id).iter().any(|a| matches!(a.object_type, ObjectType::RelationshipDependent { .. })))
+            .map(|weak| Finding {
+                severity: Severity::Error,
+                message: format!("weak entity {} has no identifying relationship", label(weak)),
+                objects: vec![weak.id]
+            })
+            .collect()
+    }
+
+    // the first entity linked to a polymorph is its supertype, or the category for a union
+    fn polymorphs(&self) -> Vec<Finding> {
+        self.objects.objects.iter()
+            .filter(|object| matches!(object.object_type, ObjectType::Polymorph { .. }))
+            .filter(|polymorph| !self.neighbors(polymorph.id).iter().any(|a| is_entity(a)))
+            .map(|polymorph| Finding {
+                severity: Severity::Error,
+                message: format!("polymorph {} has no supertype", label(polymorph)),
+                objects: vec![polymorph.id]
+            })
+            .collect()
+    }
+
+    // entities, relationships and polymorphs need unique names, attributes only need to be unique on their owner
+    fn duplicate_names(&self) -> Vec<Finding> {
+        let mut groups: HashMap<(Option<u32>, String), Vec<u32>> = HashMap::new();
+        self.objects.objects.iter()
+            .filter(|object| !object.name.is_empty())
+            .for_each(|object| {
+                let owners = if is_attribute(object) {
+                    self.neighbors(object.id).into_iter().filter(|a| !is_attribute(a)).map(|a| Some(a.id)).collect::<Vec<Option<u32>>>()
+                } else { vec![None] };
+                owners.into_iter().for_each(|owner| groups.entry((owner, object.name.to_lowercase())).or_default().push(object.id));
+            });
+
+        let mut findings = groups.into_iter()
+            .filter(|(_, ids)| ids.len() > 1)
+            .map(|((owner, _), ids)| {
+                let name = self.object(ids[0]).map(label).unwrap_or_default();
+                let message = match owner.and_then(|owner| self.object(owner)) {
+                    Some(owner) => format!("{} has {} attributes named {name}", label(owner), ids.len()),
+                    None => format!("{} objects are named {name}", ids.len())
+                };
+                Finding { severity: Severity::Error, message, objects: ids }
+            })
+            .collect::<Vec<Finding>>();
+        findings.sort_by_key(|finding| finding.objects.clone());
+        findings
+    }
+
+    // entities without an id attribute, subtypes and union categories take theirs from elsewhere
+    fn keys(&self) -> Vec<Finding> {
+        self.objects.objects.iter()
+            .filter(|object| object.object_type == ObjectType::Entity)
+            .filter(|entity| !self.has_key(entity.id, &mut vec![]))
+            .filter(|entity| !self.inherits_key(entity.id))
+            .map(|entity| Finding {
+                severity: Severity::Warning,
+                message: format!("entity {} has no ID attribute", label(entity)),
+                objects: vec![entity.id]
+            })
+            .collect()
+    }
+
+    // trees that break the rules of their kind
+    fn trees(&self) -> Vec<Finding> {
+        self.objects.trees.iter()
+            .flat_map(|tree| tree.root.check().into_iter().map(|violation| Finding {
+                severity: Severity::Error,
+                message: format!("{} #{} {violation}", tree.root.kind(), tree.id),
+                objects: vec![tree.id]
+            }))
+            .collect()
+    }
+
+    fn has_key(&self, id: u32, visited: &mut Vec<u32>) -> bool {
+        visited.push(id);
+        self.neighbors(id).into_iter()
+            .filter(|a| is_attribute(a) && !visited.contains(&a.id))
+            .collect::<Vec<&Object>>()
+            .into_iter()
+            .any(|attribute| {
+                matches!(attribute.object_type, ObjectType::Parameter { is_id: true } | ObjectType::FunctionParameter { is_id: true })
+                    || self.has_key(attribute.id, visited)
+            })
+    }
+
+    fn inherits_key(&self, id: u32) -> bool {
+        self.neighbors(id).into_iter().any(|polymorph| {
+            let ObjectType::Polymorph { poly } = &polymorph.object_type else { return false };
+            let first = self.neighbors(polymorph.id).into_iter().find(|a| is_entity(a)).map(|a| a.id);
+            match poly {
+                Polymorph::Union => first == Some(id),
+                Polymorph::Disjoint | Polymorph::Overlapping => first != Some(id)
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bminustree::{BMinusNode, BTree, KeyOrder, TreeOptions, TreeRoot};
+    use crate::dsl;
+
+    fn messages(objects: &Objects, severity: Severity) -> Vec<String> {
+        validate(objects).into_iter().filter(|finding| finding.severity == severity).map(|finding| finding.message).collect()
+    }
+
+    fn errors(text: &str) -> Vec<String> { messages(&dsl::parse(text).unwrap(), Severity::Error) }
+    fn warnings(text: &str) -> Vec<String> { messages(&dsl::parse(text).unwrap(), Severity::Warning) }
+
+    #[test]
+    fn links_to_missing_objects_are_errors() {
+        let mut objects = dsl::parse("entity A { attribute aid id }").unwrap();
+        assert!(validate(&objects).is_empty());
+
+        objects.link(1, 7);
+        let findings = validate(&objects);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].message, "link 1 - 7 points at missing object #7");
+        assert_eq!(findings[0].objects, vec![1]);
+    }
+
+    #[test]
+    fn relationships_need_two_entities() {
+        let entities = "entity A { attribute aid id } entity B { attribute bid id } relationship R 1:N";
+        assert!(errors(&format!("{entities} link A -- R link R -- B")).is_empty());
+        // the same entity twice is a recursive relationship
+        assert!(errors(&format!("{entities} link A -- R link R -- A")).is_empty());
+
+        assert_eq!(errors(&format!("{entities} link A -- R")), ["relationship R connects 1 entity, it needs at least two"]);
+        assert_eq!(errors(entities), ["relationship R connects 0 entities, it needs at least two"]);
+    }
+
+    #[test]
+    fn attributes_need_exactly_one_owner() {
+        assert!(errors("entity A { attribute aid id attribute address { attribute city attribute street } }").is_empty());
+
+        assert_eq!(errors("entity A { attribute aid id } attribute lost"), ["attribute lost is not attached to anything"]);
+        assert_eq!(
+            errors("entity A { attribute aid id attribute shared } entity B { attribute bid id } link B -- shared"),
+            ["attribute shared is attached to 2 owners: B, A"]
+        );
+    }
+
+    #[test]
+    fn only_attributes_nearer_an_entity_own_an_attribute() {
+        // attributes next to each other at the same depth do not own each other
+        assert!(errors("entity A { attribute aid id attribute x attribute y } link x -- y").is_empty());
+
+        // a part of two composite attributes has two owners
+        assert_eq!(
+            errors("entity A { attribute aid id attribute home { attribute city } attribute work } link work -- city"),
+            ["attribute city is attached to 2 owners: work, home"]
+        );
+
+        // attributes only linked to each other never reach an owner
+        assert_eq!(
+            errors("entity A { attribute aid id } attribute x { attribute y }"),
+            ["attribute x is not attached to anything", "attribute y is not attached to anything"]
+        );
+    }
+
+    #[test]
+    fn weak_entities_need_an_identifying_relationship() {
+        let entities = "entity A { attribute aid id } weak entity W { attribute part id }";
+        assert!(errors(&format!("{entities} weak relationship HAS 1:N link A -- HAS link HAS -- W")).is_empty());
+        assert_eq!(errors(&format!("{entities} relationship HAS 1:N link A -- HAS link HAS -- W")), ["weak entity W has no identifying relationship"]);
+    }
+
+    #[test]
+    fn polymorphs_need_a_supertype() {
+        assert!(errors("entity A { attribute aid id } polymorph P disjoint link A -- P").is_empty());
+        assert_eq!(errors("polymorph P disjoint"), ["polymorph P has no supertype"]);
+    }
+
+    #[test]
+    fn names_are_unique_ignoring_case() {
+        // attributes only have to be unique on their owner
+        assert!(errors("entity A { attribute name id } entity B { attribute name id }").is_empty());
+
+        assert_eq!(errors("entity A #1 { attribute aid id } entity a #2 { attribute aid2 id }"), ["2 objects are named A"]);
+        assert_eq!(errors("entity A { attribute aid id attribute x attribute X }"), ["A has 2 attributes named x"]);
+    }
+
+    #[test]
+    fn entities_without_an_id_are_warned_about() {
+        assert!(warnings("entity A { attribute aid id }").is_empty());
+        // an id inside a composite attribute counts
+        assert!(warnings("entity A { attribute code { attribute part id } }").is_empty());
+        assert_eq!(warnings("entity A { attribute name }"), ["entity A has no ID attribute"]);
+    }
+
+    #[test]
+    fn subtypes_and_union_categories_inherit_their_id() {
+        assert_eq!(
+            warnings("entity SUPER entity SUB polymorph P disjoint link SUPER -- P link P -- SUB"),
+            ["entity SUPER has no ID attribute"]
+        );
+        // a union is the other way around, the category comes first and takes its id from the others
+        assert_eq!(
+            warnings("entity OWNER entity PERSON polymorph P union link OWNER -- P link P -- PERSON"),
+            ["entity PERSON has no ID attribute"]
+        );
+    }
+
+    #[test]
+    fn broken_trees_are_errors() {
+        let mut objects = Objects::default();
+        objects.trees.push(BTree::new(1, 0.0, 0.0, TreeRoot::create((1 .. 10).map(|key| key.to_string()).collect(), &TreeOptions::default())));
+        assert!(validate(&objects).is_empty());
+
+        let leaf = |keys: &[&str]| BMinusNode { keys: keys.iter().map(|key| key.to_string()).collect(), children: vec![], order: 3, ordering: KeyOrder::Numeric };
+        let broken = BMinusNode { keys: vec!["5".into()], children: vec![leaf(&["6"]), leaf(&["7"])], order: 3, ordering: KeyOrder::Numeric };
+        objects.trees.push(BTree::new(2, 0.0, 0.0, TreeRoot::BMinus(broken)));
+        let findings = validate(&objects);
+        assert!(!findings.is_empty());
+        assert!(findings.iter().all(|finding| finding.severity == Severity::Error && finding.objects == vec![2]), "{findings:?}");
+        assert!(findings[0].message.starts_with("B-tree #2 "), "{}", findings[0].message);
+    }
+}
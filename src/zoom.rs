@@ -0,0 +1,90 @@
+use egui::{epaint::Fonts, Pos2, Rect, Shape, Stroke};
+
+pub const MIN_ZOOM: f32 = 0.1;
+pub const MAX_ZOOM: f32 = 5.0;
+
+// space left around everything when zooming to fit
+const FIT_MARGIN: f32 = 40.0;
+
+// the zoom that fits an area in the canvas with some space around it
+pub fn fit(bounds: Rect, clip: Rect) -> f32 {
+    let zoom = ((clip.width() - FIT_MARGIN * 2.0) / bounds.width()).min((clip.height() - FIT_MARGIN * 2.0) / bounds.height());
+    if zoom.is_finite() && zoom > 0.0 { zoom.clamp(MIN_ZOOM, MAX_ZOOM) } else { 1.0 }
+}
+
+// scales shapes drawn at the normal size around a point, text is laid out again at the scaled font size
+pub fn zoom_shapes(fonts: &Fonts, shapes: &mut [Shape], origin: Pos2, zoom: f32) {
+    shapes.iter_mut().for_each(|shape| zoom_shape(fonts, shape, origin, zoom));
+}
+
+fn zoom_shape(fonts: &Fonts, shape: &mut Shape, origin: Pos2, zoom: f32) {
+    let point = |pos: &mut Pos2| *pos = origin + (*pos - origin) * zoom;
+    let stroke = |stroke: &mut Stroke| stroke.width *= zoom;
+    match shape {
+        Shape::Noop | Shape::Callback(_) => {},
+        Shape::Vec(shapes) => zoom_shapes(fonts, shapes, origin, zoom),
+        Shape::Circle(circle) => {
+            point(&mut circle.center);
+            circle.radius *= zoom;
+            stroke(&mut circle.stroke);
+        },
+        Shape::LineSegment { points, stroke: line } => {
+            points.iter_mut().for_each(point);
+            stroke(line);
+        },
+        Shape::Path(path) => {
+            path.points.iter_mut().for_each(point);
+            stroke(&mut path.stroke);
+        },
+        Shape::Rect(rect) => {
+            point(&mut rect.rect.min);
+            point(&mut rect.rect.max);
+            stroke(&mut rect.stroke);
+        },
+        Shape::Text(text) => {
+            let mut job = (*text.galley.job).clone();
+            job.sections.iter_mut().for_each(|section| section.format.font_id.size *= zoom);
+            text.galley = fonts.layout_job(job);
+            point(&mut text.pos);
+        },
+        Shape::Mesh(mesh) => mesh.vertices.iter_mut().for_each(|vertex| point(&mut vertex.pos)),
+        Shape::QuadraticBezier(curve) => {
+            curve.points.iter_mut().for_each(point);
+            stroke(&mut curve.stroke);
+        },
+        Shape::CubicBezier(curve) => {
+            curve.points.iter_mut().for_each(point);
+            stroke(&mut curve.stroke);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use egui::{pos2, vec2};
+
+    fn area(width: f32, height: f32) -> Rect { Rect::from_center_size(pos2(0.0, 0.0), vec2(width, height)) }
+
+    #[test]
+    fn the_tighter_side_decides_the_zoom() {
+        assert_eq!(fit(area(100.0, 100.0), area(480.0, 280.0)), 2.0);
+        assert_eq!(fit(area(400.0, 100.0), area(480.0, 280.0)), 1.0);
+        // where the area is makes no difference
+        assert_eq!(fit(area(100.0, 100.0).translate(vec2(500.0, -300.0)), area(480.0, 280.0)), 2.0);
+    }
+
+    #[test]
+    fn zoom_stays_within_its_limits() {
+        assert_eq!(fit(area(1.0, 1.0), area(1280.0, 720.0)), MAX_ZOOM);
+        assert_eq!(fit(area(100_000.0, 100_000.0), area(1280.0, 720.0)), MIN_ZOOM);
+    }
+
+    #[test]
+    fn nothing_to_fit_keeps_the_normal_size() {
+        assert_eq!(fit(Rect::NOTHING, area(1280.0, 720.0)), 1.0);
+        assert_eq!(fit(area(0.0, 0.0), area(1280.0, 720.0)), 1.0);
+        // a canvas smaller than the margins has no room at all
+        assert_eq!(fit(area(100.0, 100.0), area(60.0, 60.0)), 1.0);
+    }
+}